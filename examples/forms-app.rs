@@ -0,0 +1,175 @@
+//! A small to-do list application where items are updated and deleted using
+//! plain HTML forms.
+//!
+//! Run it with `cargo run --example forms-app` and visit http://127.0.0.1:3000
+//!
+//! Browsers can only submit forms as GET or POST, so the update forms post to
+//! a URL with a `_method` query parameter, and the delete forms carry it in a
+//! hidden input, and the middleware turns them into PUT and DELETE requests.
+//! The parameter is stripped from the URL before the handler sees it.
+//! After each change the application responds with a 303 See Other redirect
+//! back to the list, so reloading the page afterwards doesn't submit the form
+//! again.
+//!
+//! The list sets a `SameSite=Lax` session cookie, and only requests carrying
+//! it are overridden, so forms on other sites, whose posts browsers send
+//! without the cookie, can't update or delete items.
+//!
+
+use hyper::header::{CONTENT_TYPE, LOCATION, SET_COOKIE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use hyper_method_override_middleware::MethodOverrideMiddleware;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use url::form_urlencoded;
+
+/// The cookie that overridden requests must carry.
+const SESSION_COOKIE: &str = "session";
+
+#[derive(Default)]
+struct Items {
+    next_id: u64,
+    names: BTreeMap<u64, String>,
+}
+
+type Store = Arc<Mutex<Items>>;
+
+async fn handle(store: Store, req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+    let path = req.uri().path().to_string();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (req.method().clone(), segments.as_slice()) {
+        (Method::GET, []) => Ok(index(&store.lock().unwrap())),
+
+        (Method::POST, ["items"]) => {
+            let name = form_name(req).await?;
+            let mut items = store.lock().unwrap();
+            items.next_id += 1;
+            let id = items.next_id;
+            items.names.insert(id, name);
+            Ok(see_other())
+        }
+
+        // Reached by POSTing to /items/:id?_method=PUT
+        (Method::PUT, ["items", id]) => {
+            let id = id.parse().ok();
+            let name = form_name(req).await?;
+            let mut items = store.lock().unwrap();
+            match id.and_then(|id| items.names.get_mut(&id)) {
+                Some(existing) => *existing = name,
+                None => return Ok(status(StatusCode::NOT_FOUND)),
+            }
+            Ok(see_other())
+        }
+
+        // Reached by POSTing to /items/:id with a hidden `_method` input
+        (Method::DELETE, ["items", id]) => {
+            let mut items = store.lock().unwrap();
+            match id.parse().ok().and_then(|id| items.names.remove(&id)) {
+                Some(_) => Ok(see_other()),
+                None => Ok(status(StatusCode::NOT_FOUND)),
+            }
+        }
+
+        (_, []) | (_, ["items"]) | (_, ["items", _]) => Ok(status(StatusCode::METHOD_NOT_ALLOWED)),
+
+        _ => Ok(status(StatusCode::NOT_FOUND)),
+    }
+}
+
+async fn form_name(req: Request<Body>) -> Result<String, hyper::Error> {
+    let body = hyper::body::to_bytes(req.into_body()).await?;
+    Ok(form_urlencoded::parse(&body)
+        .find(|(name, _)| name == "name")
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_default())
+}
+
+fn index(items: &Items) -> Response<Body> {
+    let mut html = String::from(
+        "<!doctype html>\n<title>Items</title>\n<h1>Items</h1>\n\
+         <form method=\"POST\" action=\"/items\">\n\
+         <input name=\"name\" placeholder=\"New item\">\n\
+         <button type=\"submit\">Add item</button>\n\
+         </form>\n<ul>\n",
+    );
+
+    for (id, name) in &items.names {
+        html.push_str(&format!(
+            "<li>\n\
+             <form method=\"POST\" action=\"/items/{id}?_method=PUT\">\n\
+             <input name=\"name\" value=\"{name}\">\n\
+             <button type=\"submit\">Update item</button>\n\
+             </form>\n\
+             <form method=\"POST\" action=\"/items/{id}\">\n\
+             <input type=\"hidden\" name=\"_method\" value=\"DELETE\">\n\
+             <button type=\"submit\">Delete item</button>\n\
+             </form>\n\
+             </li>\n",
+            id = id,
+            name = escape(name),
+        ));
+    }
+
+    html.push_str("</ul>\n");
+
+    // There are no accounts, so the session only marks browsers that have
+    // loaded the list.
+    let cookie = format!("{}=1; HttpOnly; SameSite=Lax; Path=/", SESSION_COOKIE);
+    Response::builder()
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .header(SET_COOKIE, cookie)
+        .body(html.into())
+        .unwrap()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn see_other() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::SEE_OTHER)
+        .header(LOCATION, "/")
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(status.to_string().into())
+        .unwrap()
+}
+
+#[tokio::main]
+async fn main() {
+    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
+    let store = Store::default();
+
+    let make_svc = make_service_fn(move |_conn| {
+        let store = store.clone();
+        async move {
+            let handler = service_fn(move |req| handle(store.clone(), req));
+            let service = MethodOverrideMiddleware::builder()
+                .strip_param(true)
+                .require_cookie(SESSION_COOKIE)
+                .form_body(true)
+                .build_with_body(handler);
+            Ok::<_, Infallible>(service)
+        }
+    });
+
+    let server = Server::bind(&addr).serve(make_svc);
+    println!("Listening on http://{}", addr);
+
+    if let Err(e) = server.await {
+        eprintln!("server error: {}", e);
+    }
+}
@@ -0,0 +1,75 @@
+//! Feeds arbitrary override headers through the header source: values with
+//! stray whitespace left over from folded lines, non-ASCII bytes, and repeated
+//! or conflicting headers.
+//!
+//! The first byte picks the configuration, and the rest is split into headers
+//! at each newline, which can't be in a header value. The first byte of each
+//! picks its name and the rest is its value.
+
+#![no_main]
+
+use http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use http::{Method, Request};
+use hyper_method_override_middleware::{
+    MethodOverrideInfo, MethodOverrideMiddleware, OverrideSource, Precedence,
+};
+use libfuzzer_sys::fuzz_target;
+
+const NAMES: [&str; 4] = [
+    "x-http-method-override",
+    "x-http-method",
+    "x-requested-with",
+    "content-type",
+];
+
+fuzz_target!(|data: &[u8]| {
+    let (config, headers) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let strict = config & 1 != 0;
+    let mut builder = MethodOverrideMiddleware::builder()
+        .header_override(true)
+        .strict(strict)
+        .require_requested_with(config & 2 != 0);
+    if config & 4 != 0 {
+        builder = builder.precedence(Precedence::Header);
+    }
+    let sync = builder.build_sync();
+
+    let mut req = Request::post("/").body(()).unwrap();
+    if config & 8 != 0 {
+        req.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+    }
+    for header in headers.split(|&byte| byte == b'\n') {
+        let (name, value) = match header.split_first() {
+            Some((name, value)) => (NAMES[usize::from(*name) % NAMES.len()], value),
+            None => continue,
+        };
+        if let Ok(value) = HeaderValue::from_bytes(value) {
+            let name = HeaderName::from_static(name);
+            req.headers_mut().append(name, value);
+        }
+    }
+
+    match sync.apply(&mut req) {
+        Ok(true) => {
+            let allowed = [Method::PUT, Method::PATCH, Method::DELETE];
+            assert!(allowed.contains(req.method()), "{}", req.method());
+            let info = req.extensions().get::<MethodOverrideInfo>().unwrap();
+            assert_eq!(info.original, Method::POST);
+            assert_eq!(info.source, OverrideSource::Header);
+        }
+        Ok(false) => {
+            assert_eq!(req.method(), Method::POST);
+            assert!(req.extensions().get::<MethodOverrideInfo>().is_none());
+        }
+        Err(reason) => {
+            assert!(strict, "rejected outside strict mode: {:?}", reason);
+            assert_eq!(req.method(), Method::POST);
+        }
+    }
+});
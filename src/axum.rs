@@ -0,0 +1,92 @@
+//! Extractors for [axum](https://docs.rs/axum) handlers behind a
+//! `hyper1::MethodOverride` or `hyper1::BodyMethodOverride`, reading the
+//! `hyper1::MethodOverrideInfo` they add, so a handler can tell a tunnelled
+//! DELETE from one sent as a DELETE:
+//!
+//!    async fn delete_item(OverriddenMethod(info): OverriddenMethod) -> Response {
+//!        match info {
+//!            Some(info) => ..., // Sent as info.original, from info.source
+//!            None => ...,       // Sent as a DELETE
+//!        }
+//!    }
+//!
+//! Neither of them rejects requests, so they can be used in any handler.
+//!
+
+use crate::hyper1::MethodOverrideInfo;
+use axum_core::extract::FromRequestParts;
+use http1::request::Parts;
+use http1::Method;
+use std::convert::Infallible;
+
+/// The method the request was sent with, before any override.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalMethod(pub Method);
+
+/// How the request's method was overridden, or `None` if it was sent with
+/// the method it has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverriddenMethod(pub Option<MethodOverrideInfo>);
+
+impl<S: Send + Sync> FromRequestParts<S> for OriginalMethod {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+        let original = match parts.extensions.get::<MethodOverrideInfo>() {
+            Some(info) => info.original.clone(),
+            None => parts.method.clone(),
+        };
+        Ok(Self(original))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for OverriddenMethod {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Infallible> {
+        Ok(Self(parts.extensions.get::<MethodOverrideInfo>().cloned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MethodOverrideMiddleware, OverrideSource, FORM_CONTENT_TYPE};
+    use http1::{Request, Response};
+    use hyper1::service::{service_fn, Service};
+
+    async fn handle(req: Request<String>) -> Result<Response<String>, Infallible> {
+        let (mut parts, _) = req.into_parts();
+        let OriginalMethod(original) = OriginalMethod::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        let OverriddenMethod(info) = OverriddenMethod::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        let source = info.map(|info| info.source);
+        Ok(Response::new(format!(
+            "{} {} {:?}",
+            parts.method, original, source
+        )))
+    }
+
+    #[tokio::test]
+    async fn extractor_test() {
+        let service = MethodOverrideMiddleware::builder().build_hyper1(service_fn(handle));
+        let send = |method: Method, uri: &str| {
+            let req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", FORM_CONTENT_TYPE)
+                .body(String::new())
+                .unwrap();
+            service.call(req)
+        };
+
+        let res = send(Method::POST, "/?_method=DELETE").await.unwrap();
+        let source = Some(OverrideSource::QueryParam);
+        assert_eq!(res.into_body(), format!("DELETE POST {:?}", source));
+        let res = send(Method::DELETE, "/").await.unwrap();
+        assert_eq!(res.into_body(), "DELETE DELETE None");
+    }
+}
@@ -0,0 +1,232 @@
+//! The middleware for inner services whose request bodies it can read and
+//! replace, which reading the method from form bodies and `BodyPolicy::Strip`
+//! both need. Built with `Builder::build_with_body`:
+//!
+//!    let service = MethodOverrideMiddleware::builder()
+//!        .form_body(true)
+//!        .build_with_body(service);
+//!
+
+use crate::{
+    dispatch, multipart_boundary, strip_body, trace_body_error, Builder, Options, OverrideSource,
+    RejectReason, ResponseFuture,
+};
+use bytes::{Buf, Bytes};
+use http::request::Parts;
+use http::{Request, Response};
+use pin_project_lite::pin_project;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// A `MethodOverrideMiddleware` that can also read the method from form
+/// bodies and strip the bodies of overridden requests. The request body must
+/// implement `http_body::Body`, `From<Bytes>`, and `Default`, and the inner
+/// service `Clone`, as it is only called once a form body has been read.
+#[derive(Debug, Clone)]
+pub struct BodyMethodOverride<T> {
+    inner_service: T,
+    options: Arc<Options>,
+}
+
+impl Builder {
+    /// Wraps the inner service in a `BodyMethodOverride` with this
+    /// configuration, which `Builder::form_body` and `BodyPolicy::Strip` need.
+    pub fn build_with_body<T>(self, inner_service: T) -> BodyMethodOverride<T> {
+        BodyMethodOverride {
+            inner_service,
+            options: Arc::new(self.into_options()),
+        }
+    }
+}
+
+impl<InnerService, Body, ResBody> Service<Request<Body>> for BodyMethodOverride<InnerService>
+where
+    InnerService: Service<Request<Body>, Response = Response<ResBody>> + Clone,
+    Body: http_body::Body + From<Bytes> + Default,
+    Body::Error: fmt::Display,
+    ResBody: Default,
+{
+    type Response = InnerService::Response;
+    type Error = InnerService::Error;
+    type Future = BodyResponseFuture<InnerService, Body>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner_service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let candidate = self.options.is_candidate(&req);
+        let overridden = match self.options.apply_override(&mut req, candidate) {
+            Ok(overridden) => overridden,
+            Err(reason) => {
+                let future = ResponseFuture::reject(reason, &self.options);
+                return BodyResponseFuture::inner(future);
+            }
+        };
+
+        if !overridden && self.options.should_read_body(&req, candidate) {
+            // The inner service can only be called once the body has been
+            // read, so the future takes the service that has been readied.
+            let clone = self.inner_service.clone();
+            let service = std::mem::replace(&mut self.inner_service, clone);
+            return BodyResponseFuture::buffer(service, self.options.clone(), req);
+        }
+
+        let hint = self.options.should_hint(overridden, candidate);
+        let future = dispatch(
+            &mut self.inner_service,
+            &self.options,
+            req,
+            overridden,
+            hint,
+            strip_body,
+        );
+        BodyResponseFuture::inner(future)
+    }
+}
+
+pin_project! {
+    /// The future returned by `BodyMethodOverride`, which reads a form body
+    /// before calling the inner service when the method may be given in it.
+    pub struct BodyResponseFuture<S, Body>
+    where
+        S: Service<Request<Body>>,
+    {
+        #[pin]
+        kind: Kind<S, Body>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<S, Body>
+    where
+        S: Service<Request<Body>>,
+    {
+        // Reading a form body to look for the parameter in, before the inner
+        // service can be called.
+        Buffering {
+            #[pin]
+            body: Body,
+            buffer: Vec<u8>,
+            // The boundary of a multipart body, rather than a URL-encoded one.
+            boundary: Option<String>,
+            parts: Option<Parts>,
+            service: Option<S>,
+            options: Arc<Options>,
+        },
+        Inner {
+            #[pin]
+            future: ResponseFuture<S::Future>,
+        },
+    }
+}
+
+impl<S, Body> BodyResponseFuture<S, Body>
+where
+    S: Service<Request<Body>>,
+{
+    fn inner(future: ResponseFuture<S::Future>) -> Self {
+        Self {
+            kind: Kind::Inner { future },
+        }
+    }
+
+    fn buffer(service: S, options: Arc<Options>, req: Request<Body>) -> Self {
+        let boundary = multipart_boundary(&req);
+        let (parts, body) = req.into_parts();
+        Self {
+            kind: Kind::Buffering {
+                body,
+                buffer: Vec::new(),
+                boundary,
+                parts: Some(parts),
+                service: Some(service),
+                options,
+            },
+        }
+    }
+}
+
+impl<S, Body, ResBody> Future for BodyResponseFuture<S, Body>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>>,
+    Body: http_body::Body + From<Bytes> + Default,
+    Body::Error: fmt::Display,
+    ResBody: Default,
+{
+    type Output = Result<Response<ResBody>, S::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let (mut body, buffer, boundary, parts, service, options) =
+                match self.as_mut().project().kind.project() {
+                    KindProj::Buffering {
+                        body,
+                        buffer,
+                        boundary,
+                        parts,
+                        service,
+                        options,
+                    } => (body, buffer, boundary, parts, service, options),
+                    KindProj::Inner { future } => return future.poll(cx),
+                };
+
+            match body.as_mut().poll_data(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(mut chunk))) => {
+                    while chunk.has_remaining() {
+                        let bytes = chunk.bytes();
+                        buffer.extend_from_slice(bytes);
+                        let read = bytes.len();
+                        chunk.advance(read);
+                    }
+                    if buffer.len() > options.body_limit(boundary.as_deref()) {
+                        let reason = RejectReason::BodyTooLarge;
+                        let future = reject(options, parts.as_ref().unwrap(), reason);
+                        self.set(Self::inner(future));
+                    }
+                    continue;
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    let parts = parts.as_ref().unwrap();
+                    trace_body_error(parts.uri.path(), &error);
+                    let future = reject(options, parts, RejectReason::BodyUnreadable);
+                    self.set(Self::inner(future));
+                    continue;
+                }
+                Poll::Ready(None) => (),
+            }
+
+            let body = Bytes::from(std::mem::take(buffer));
+            let mut req = Request::from_parts(parts.take().unwrap(), Body::from(body.clone()));
+            let overridden = match options.apply_body_override(&mut req, &body, boundary.as_deref())
+            {
+                Ok(overridden) => overridden,
+                Err(reason) => {
+                    let future = ResponseFuture::reject(reason, options);
+                    self.set(Self::inner(future));
+                    continue;
+                }
+            };
+
+            // Only candidate requests have their body read.
+            let hint = options.should_hint(overridden, true);
+            let mut service = service.take().unwrap();
+            let future = dispatch(&mut service, options, req, overridden, hint, strip_body);
+            self.set(Self::inner(future));
+        }
+    }
+}
+
+/// Reports a request rejected while its form body was read, and returns the
+/// future of the response to it.
+fn reject<F>(options: &Arc<Options>, parts: &Parts, reason: RejectReason) -> ResponseFuture<F> {
+    let (method, path) = (parts.method.as_str(), parts.uri.path());
+    options.report_rejection(method, OverrideSource::FormBody, path, reason);
+    ResponseFuture::reject(reason, options)
+}
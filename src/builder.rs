@@ -0,0 +1,413 @@
+use crate::{
+    BodyPolicy, MethodOverrideInfo, MethodOverrideMiddleware, MethodOverrideRejection,
+    MethodResolver, Options, OverrideHook, OverrideSource, Precedence, Preset, RejectHook,
+    RequestFilter, RequestHead, SyncMethodOverride, DEFAULT_REQUESTED_WITH,
+};
+use http::header::HeaderValue;
+use http::Method;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Configures a `MethodOverrideMiddleware`. Created with
+/// `MethodOverrideMiddleware::builder`:
+///
+///    let service = MethodOverrideMiddleware::builder()
+///        .param_name("_override")
+///        .allow_method(Method::OPTIONS)
+///        .build(service);
+///
+/// Methods can be given as the `Method` of either version of the `http` crate,
+/// or by name. Methods given by name must be valid method tokens, or the
+/// builder panics.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    options: Options,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a `Builder` configured with the given preset, which the other
+    /// methods can then change.
+    pub fn from_preset(preset: Preset) -> Self {
+        let rails_methods = ["PUT", "PATCH", "DELETE", "OPTIONS", "LINK", "UNLINK"];
+        let builder = Self::new();
+        match preset {
+            Preset::RailsCompatible => builder
+                .form_body(true)
+                .header_override(true)
+                .allowed_methods(rails_methods),
+            Preset::ExpressCompatible => builder.header_override(true).check_content_type(false),
+            Preset::LaravelCompatible => builder
+                .form_body(true)
+                .header_override(true)
+                .check_content_type(false)
+                .allow_method(Method::OPTIONS),
+            Preset::StrictSecure => builder
+                .strict(true)
+                .header_override(false)
+                .check_content_type(true),
+            Preset::Permissive => builder
+                .form_body(true)
+                .header_override(true)
+                .check_content_type(false)
+                .allowed_methods(rails_methods),
+        }
+    }
+
+    /// Sets the name of the query parameter, or form field, that holds the
+    /// method to override to. Defaults to `_method`.
+    pub fn param_name(mut self, name: impl Into<String>) -> Self {
+        self.options.param_name = Cow::Owned(name.into());
+        self
+    }
+
+    /// Allows overriding to the given method, as well as to those already
+    /// allowed. PUT, PATCH, and DELETE are allowed by default.
+    pub fn allow_method(mut self, method: impl AsRef<str>) -> Self {
+        let method = to_method(method);
+        if !self.options.allowed_methods.contains(&method) {
+            self.options.allowed_methods.to_mut().push(method);
+        }
+        self
+    }
+
+    /// Sets the methods that can be overridden to, replacing the default of
+    /// PUT, PATCH, and DELETE.
+    pub fn allowed_methods<M: AsRef<str>>(mut self, methods: impl IntoIterator<Item = M>) -> Self {
+        self.options.allowed_methods = Cow::Owned(Vec::new());
+        for method in methods {
+            self = self.allow_method(method);
+        }
+        self
+    }
+
+    /// Sets the methods of the requests that can be overridden. Defaults to
+    /// POST only.
+    pub fn source_methods<M: AsRef<str>>(mut self, methods: impl IntoIterator<Item = M>) -> Self {
+        self.options.source_methods = Cow::Owned(methods.into_iter().map(to_method).collect());
+        self
+    }
+
+    /// When enabled, the method can also be given in an `X-HTTP-Method-Override`
+    /// or `X-HTTP-Method` header, as sent by many JavaScript clients and
+    /// proxies. The same methods are allowed as for the query parameter.
+    ///
+    /// Disabled by default.
+    pub fn header_override(mut self, enabled: bool) -> Self {
+        self.options.header_override = enabled;
+        self
+    }
+
+    /// When enabled, override headers are only read from requests with an
+    /// `X-Requested-With: XMLHttpRequest` header, as sent by JavaScript
+    /// clients such as jQuery, or the value given to `requested_with`. Other
+    /// requests are treated as if they had no override header. Values are
+    /// compared case-insensitively.
+    ///
+    /// Disabled by default.
+    pub fn require_requested_with(mut self, enabled: bool) -> Self {
+        self.options.requested_with = if enabled {
+            Some(Cow::Borrowed(DEFAULT_REQUESTED_WITH))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Requires override headers to come with an `X-Requested-With` header
+    /// of the given value, rather than `XMLHttpRequest`, enabling
+    /// `require_requested_with`.
+    pub fn requested_with(mut self, value: impl Into<String>) -> Self {
+        self.options.requested_with = Some(Cow::Owned(value.into()));
+        self
+    }
+
+    /// Keeps overriding requests with the method given in the source, but marks
+    /// the source as deprecated so its remaining users can be found before it
+    /// is disabled. With the `tracing` feature each override with a method
+    /// from it is also recorded as a `WARN` level event, and with
+    /// `deprecation_header` the responses to those requests are marked.
+    pub fn deprecate_source(mut self, source: OverrideSource) -> Self {
+        if !self.options.deprecated_sources.contains(&source) {
+            self.options.deprecated_sources.push(source);
+        }
+        self
+    }
+
+    /// Adds a `Deprecation` header with the given value, such as
+    /// `@1767225600` for the date the source was deprecated on, to the
+    /// middleware's responses to requests overridden with a method from a
+    /// deprecated source.
+    ///
+    /// Panics if the value isn't a valid header value.
+    pub fn deprecation_header(mut self, value: impl Into<String>) -> Self {
+        let value = value.into();
+        let header = HeaderValue::from_str(&value)
+            .unwrap_or_else(|_| panic!("{:?} is not a valid header value", value));
+        self.options.deprecation_header = Some(header);
+        self
+    }
+
+    /// Sets which of the header and the query parameter is used when a request
+    /// gives a method in both. Defaults to `Precedence::Query`.
+    pub fn precedence(mut self, precedence: Precedence) -> Self {
+        self.options.precedence = precedence;
+        self
+    }
+
+    /// Sets the function that turns a `_method` value, or that of an override
+    /// header, into a method, for applications with their own conventions:
+    ///
+    ///    let service = MethodOverrideMiddleware::builder()
+    ///        .method_resolver(|value| match value {
+    ///            "destroy" => Some(Method::DELETE),
+    ///            "update" => Some(Method::PATCH),
+    ///            _ => Method::from_bytes(value.as_bytes()).ok(),
+    ///        })
+    ///        .build(service);
+    ///
+    /// Values it returns `None` for are treated as not being methods, and the
+    /// methods it returns must still be allowed. By default values are
+    /// matched case-insensitively, so `delete` and `Delete` both give DELETE.
+    pub fn method_resolver(
+        mut self,
+        resolver: impl Fn(&str) -> Option<Method> + Send + Sync + 'static,
+    ) -> Self {
+        self.options.method_resolver = Some(MethodResolver(Arc::new(resolver)));
+        self
+    }
+
+    /// When enabled, the `_method` query parameter is only used for requests
+    /// with one of the allowed content types, which by default are those of a
+    /// browser's form submission, `application/x-www-form-urlencoded` and
+    /// `multipart/form-data`. This stops a URL with the parameter turning
+    /// other requests, such as JSON API calls, into another method. Override
+    /// headers can't be sent cross-site without CORS, so aren't checked.
+    ///
+    /// Enabled by default.
+    pub fn check_content_type(mut self, enabled: bool) -> Self {
+        self.options.check_content_type = enabled;
+        self
+    }
+
+    /// Sets the content types of the requests the `_method` query parameter is
+    /// used for, replacing the default of `application/x-www-form-urlencoded`
+    /// and `multipart/form-data`. Parameters such as `charset` are ignored.
+    pub fn content_types<T: Into<String>>(
+        mut self,
+        content_types: impl IntoIterator<Item = T>,
+    ) -> Self {
+        let content_types = content_types
+            .into_iter()
+            .map(|content_type| Cow::Owned(content_type.into()));
+        self.options.content_types = Cow::Owned(content_types.collect());
+        self
+    }
+
+    /// When enabled, requests that give a method that can't be overridden to
+    /// are rejected without calling the inner service, rather than being
+    /// passed on unchanged. A value that isn't a method, or has malformed
+    /// percent encoding, gets a 400 Bad Request response, and a method that
+    /// isn't allowed gets a 405 Method Not Allowed response. Its `Allow` header
+    /// lists the source methods and the methods that can be overridden to.
+    ///
+    /// Disabled by default.
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.options.strict = enabled;
+        self
+    }
+
+    /// When enabled, the `_method` parameter is removed from the URI of
+    /// requests overridden with it, so it doesn't reach routing, query
+    /// extractors, or logs. The other parameters are kept in order.
+    ///
+    /// Enabled by default.
+    pub fn strip_param(mut self, enabled: bool) -> Self {
+        self.options.strip_param = enabled;
+        self
+    }
+
+    /// When enabled, a 405 Method Not Allowed response to a request that could
+    /// have been overridden, but wasn't, gets an `X-Method-Override-Supported`
+    /// header listing the methods that can be given in the `_method` query
+    /// parameter, so clients that can only send POST requests can discover the
+    /// override.
+    ///
+    /// Disabled by default.
+    pub fn hint_supported_methods(mut self, hint: bool) -> Self {
+        self.options.hint_supported_methods = hint;
+        self
+    }
+
+    /// Only override requests that carry a cookie with the given name, such as
+    /// the application's session cookie. Cross-site form posts made from a
+    /// logged out browser are then left as POST requests.
+    pub fn require_cookie(mut self, name: impl Into<String>) -> Self {
+        self.options.required_cookie = Some(name.into());
+        self
+    }
+
+    /// Only override requests whose path is the given prefix or below it, such
+    /// as `/admin` for `/admin` and `/admin/users` but not `/administrator`.
+    /// Can be given more than once to allow several prefixes. All paths are
+    /// allowed by default.
+    ///
+    /// Paths are compared once percent-encoded unreserved characters have been
+    /// decoded, repeated slashes collapsed, and `.` and `..` segments removed,
+    /// so `/%61dmin` and `//admin` are below `/admin` too.
+    pub fn include_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.options.include_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Never override requests whose path is the given prefix or below it, such
+    /// as `/api` for a JSON API whose clients can send any method. Takes
+    /// precedence over `include_prefix`.
+    pub fn exclude_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.options.exclude_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Only override requests the given predicate returns `true` for. It is
+    /// called once for each request of a source method, before the request's
+    /// method is looked for, along with the prefix checks, and replaces any
+    /// predicate given before.
+    ///
+    ///    let service = MethodOverrideMiddleware::builder()
+    ///        .filter(|req| !req.path().ends_with(".json"))
+    ///        .build(service);
+    ///
+    pub fn filter(
+        mut self,
+        filter: impl Fn(&RequestHead<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.options.filter = Some(RequestFilter(Arc::new(filter)));
+        self
+    }
+
+    /// Sets what happens to the body of requests overridden to the given
+    /// method. Bodies are kept for all methods by default. Stripping bodies
+    /// needs the middleware to be built with `build_with_body`.
+    pub fn body_policy(mut self, method: impl AsRef<str>, policy: BodyPolicy) -> Self {
+        let method = to_method(method);
+        let body_policies = &mut self.options.body_policies;
+        body_policies.retain(|(existing, _)| existing != method);
+        body_policies.push((method, policy));
+        self
+    }
+
+    /// When enabled, POST requests, or those of the other source methods, with
+    /// an `application/x-www-form-urlencoded` body and no `_method` query
+    /// parameter or override header have their body read to look for the
+    /// parameter there, as in a hidden form input:
+    ///
+    ///    <input type="hidden" name="_method" value="DELETE">
+    ///
+    /// With the `multipart` feature, `multipart/form-data` bodies are read
+    /// too, looking for a `_method` field that isn't a file.
+    ///
+    /// The body is then passed on to the inner service unchanged. Bodies
+    /// without a `Content-Length`, or longer than the form body limit or the
+    /// multipart body limit, are not read. The middleware must be built with
+    /// `build_with_body`.
+    ///
+    /// Disabled by default.
+    pub fn form_body(mut self, enabled: bool) -> Self {
+        self.options.form_body = enabled;
+        self
+    }
+
+    /// Sets the longest form body, in bytes, that is read to look for the
+    /// `_method` parameter. Defaults to `DEFAULT_FORM_BODY_LIMIT`.
+    pub fn form_body_limit(mut self, limit: usize) -> Self {
+        self.options.form_body_limit = limit;
+        self
+    }
+
+    /// Sets the longest `multipart/form-data` body, in bytes, that is read to
+    /// look for the `_method` field with the `multipart` feature, in place of
+    /// the form body limit. Defaults to `DEFAULT_MULTIPART_BODY_LIMIT`, so
+    /// that forms with file uploads can use a hidden input.
+    pub fn multipart_body_limit(mut self, limit: usize) -> Self {
+        self.options.multipart_body_limit = limit;
+        self
+    }
+
+    /// Calls the given function with the `MethodOverrideInfo` of each request
+    /// whose method is overridden, before the inner service is called, so
+    /// overrides can be counted in the application's metrics. Replaces any
+    /// function given before.
+    ///
+    /// With the `tracing` feature, overrides and rejected overrides are also
+    /// recorded as `DEBUG` level events.
+    pub fn on_override(
+        mut self,
+        hook: impl Fn(&MethodOverrideInfo) + Send + Sync + 'static,
+    ) -> Self {
+        self.options.on_override = Some(OverrideHook(Arc::new(hook)));
+        self
+    }
+
+    /// Calls the given function with a `MethodOverrideRejection` for each
+    /// request rejected for the method it was to be overridden to, in strict
+    /// mode or by `BodyPolicy::Reject`, before the rejection is sent. Its
+    /// `RejectReason` tells malformed percent encoding apart from values that
+    /// aren't methods. Outside strict mode it is also called for the requests
+    /// passed through with such values, with `passed_through` set. Replaces any
+    /// function given before.
+    pub fn on_reject(
+        mut self,
+        hook: impl Fn(&MethodOverrideRejection) + Send + Sync + 'static,
+    ) -> Self {
+        self.options.on_reject = Some(RejectHook(Arc::new(hook)));
+        self
+    }
+
+    /// Wraps the inner service in a middleware with this configuration.
+    ///
+    /// Panics if `form_body` is enabled or a body policy is
+    /// `BodyPolicy::Strip`, as those need `build_with_body`.
+    pub fn build<T>(self, inner_service: T) -> MethodOverrideMiddleware<T> {
+        self.assert_no_body("use Builder::build_with_body");
+        MethodOverrideMiddleware {
+            inner_service,
+            options: Arc::new(self.into_options()),
+        }
+    }
+
+    /// Returns a `SyncMethodOverride` with this configuration, for applications
+    /// that aren't built on `Service`.
+    ///
+    /// Panics if `form_body` is enabled or a body policy is
+    /// `BodyPolicy::Strip`, as it can't replace the body of a request.
+    pub fn build_sync(self) -> SyncMethodOverride {
+        self.assert_no_body("which SyncMethodOverride can't do");
+        SyncMethodOverride {
+            options: Arc::new(self.into_options()),
+        }
+    }
+
+    /// Panics if the configuration needs a middleware that can replace request
+    /// bodies, with advice on what to do instead.
+    pub(crate) fn assert_no_body(&self, advice: &str) {
+        assert!(
+            !self.options.needs_body(),
+            "form_body and BodyPolicy::Strip need the request body to be replaced, {}",
+            advice
+        );
+    }
+
+    pub(crate) fn into_options(self) -> Options {
+        self.options
+    }
+}
+
+fn to_method(method: impl AsRef<str>) -> Method {
+    let name = method.as_ref();
+    Method::from_bytes(name.as_bytes())
+        .unwrap_or_else(|_| panic!("{:?} is not a valid method name", name))
+}
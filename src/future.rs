@@ -0,0 +1,145 @@
+use crate::{Options, OverrideRequest, RejectReason, DEPRECATION_HEADER, SUPPORTED_HEADER};
+use http::header::{HeaderName, HeaderValue, ALLOW};
+use http::{Response, StatusCode};
+use pin_project_lite::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+pin_project! {
+    /// The future returned by `MethodOverrideMiddleware`, which resolves to the
+    /// inner service's response, or to the middleware's own response when it
+    /// rejects a request.
+    pub struct ResponseFuture<F> {
+        #[pin]
+        kind: Kind<F>,
+        // The options to list the supported methods from when the response is a
+        // 405 that should be hinted.
+        hint: Option<Arc<Options>>,
+        // The `Deprecation` header, when the request was overridden with a
+        // method from a deprecated source.
+        deprecation: Option<(HeaderName, HeaderValue)>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<F> {
+        Inner {
+            #[pin]
+            future: F,
+        },
+        Reject {
+            reason: RejectReason,
+            options: Arc<Options>,
+        },
+    }
+}
+
+impl<F> ResponseFuture<F> {
+    pub(crate) fn inner(
+        future: F,
+        hint: Option<Arc<Options>>,
+        deprecation: Option<(HeaderName, HeaderValue)>,
+    ) -> Self {
+        Self {
+            kind: Kind::Inner { future },
+            hint,
+            deprecation,
+        }
+    }
+
+    /// Rejects the request without calling the inner service.
+    pub(crate) fn reject(reason: RejectReason, options: &Arc<Options>) -> Self {
+        Self {
+            kind: Kind::Reject {
+                reason,
+                options: options.clone(),
+            },
+            hint: None,
+            deprecation: None,
+        }
+    }
+}
+
+impl Options {
+    /// The headers of the middleware's own responses with the given status:
+    /// an `Allow` header on 405s, which are always hinted when the hint is
+    /// enabled, as they are rejected for the method they were to be
+    /// overridden to.
+    pub(crate) fn reject_headers(&self, status: StatusCode) -> Vec<(HeaderName, HeaderValue)> {
+        let mut headers = Vec::new();
+        if status == StatusCode::METHOD_NOT_ALLOWED {
+            headers.push((ALLOW, self.allow()));
+            if self.hint_supported_methods {
+                headers.extend(self.hint_header(status));
+            }
+        }
+        headers
+    }
+
+    /// The headers added to a response with the given status from the inner
+    /// service, when the request's 405s are hinted.
+    pub(crate) fn hint_header(&self, status: StatusCode) -> Option<(HeaderName, HeaderValue)> {
+        if status == StatusCode::METHOD_NOT_ALLOWED {
+            Some((
+                HeaderName::from_static(SUPPORTED_HEADER),
+                self.supported_methods(),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// The header added to the response to a request overridden with a method
+    /// from a deprecated source, when `Builder::deprecation_header` is set.
+    pub(crate) fn deprecation_header(
+        &self,
+        req: &impl OverrideRequest,
+    ) -> Option<(HeaderName, HeaderValue)> {
+        let info = req.override_info()?;
+        if !self.deprecated_sources.contains(&info.source) {
+            return None;
+        }
+        let value = self.deprecation_header.clone()?;
+        Some((HeaderName::from_static(DEPRECATION_HEADER), value))
+    }
+}
+
+impl<F, ResBody, Error> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<ResBody>, Error>>,
+    ResBody: Default,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = match this.kind.project() {
+            KindProj::Inner { future } => match future.poll(cx) {
+                Poll::Ready(Ok(res)) => res,
+                other => return other,
+            },
+            KindProj::Reject { reason, options } => {
+                let mut res = Response::new(ResBody::default());
+                *res.status_mut() = reason.status();
+                for (name, value) in options.reject_headers(reason.status()) {
+                    res.headers_mut().insert(name, value);
+                }
+                res.extensions_mut().insert(*reason);
+                res
+            }
+        };
+
+        if let Some(options) = this.hint {
+            if let Some((name, value)) = options.hint_header(res.status()) {
+                res.headers_mut().insert(name, value);
+            }
+        }
+        if let Some((name, value)) = this.deprecation.take() {
+            res.headers_mut().insert(name, value);
+        }
+        Poll::Ready(Ok(res))
+    }
+}
@@ -0,0 +1,463 @@
+//! Method overriding for [Gotham](https://gotham.rs) applications.
+//!
+//! Gotham selects a route by method before running the route's pipelines, so
+//! to have overridden requests routed by their new method wrap the router
+//! with a `MethodOverrideHandler`:
+//!
+//!    gotham::start(addr, MethodOverrideHandler::new(router()));
+//!
+//! The `MethodOverride` middleware can be used in a pipeline instead when the
+//! application only needs handlers to see the overridden method. The router
+//! has already extracted the query string by then, though the `Uri` handlers
+//! see has the parameter removed.
+//!
+//! Overridden requests have a `MethodOverrideInfo` put in their state, as the
+//! middleware adds one to a request's extensions. Requests rejected in strict
+//! mode or by `BodyPolicy::Reject` get the same empty 400 or 405 response the
+//! middleware sends, without the handler being called.
+//!
+//! Both use the default configuration, or are configured with the same builder
+//! as the other adapters, using `Builder::build_gotham` and
+//! `Builder::gotham_middleware`.
+//!
+
+use crate::{has_cookie, strip_param, Builder, Head, MethodOverrideInfo, Options, RejectReason};
+use ::gotham::anyhow;
+use ::gotham::handler::{Handler, HandlerFuture, NewHandler};
+use ::gotham::helpers::http::response::create_empty_response;
+use ::gotham::middleware::{Middleware, NewMiddleware};
+use ::gotham::state::{FromState, State, StateData};
+use http::header::{HeaderMap, HeaderValue, COOKIE};
+use http::{Method, Uri};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A Gotham `NewHandler` that overrides the method of incoming requests before
+/// passing them to the wrapped handler, typically the application's router.
+#[derive(Debug, Clone)]
+pub struct MethodOverrideHandler<T> {
+    inner_handler: T,
+    options: SharedOptions,
+}
+
+impl<T> MethodOverrideHandler<T> {
+    pub fn new(inner_handler: T) -> Self {
+        Builder::new().build_gotham(inner_handler)
+    }
+}
+
+impl<T: NewHandler> NewHandler for MethodOverrideHandler<T> {
+    type Instance = MethodOverrideHandler<T::Instance>;
+
+    fn new_handler(&self) -> anyhow::Result<Self::Instance> {
+        let options = self.options.clone();
+        self.inner_handler
+            .new_handler()
+            .map(|inner_handler| MethodOverrideHandler {
+                inner_handler,
+                options,
+            })
+    }
+}
+
+impl<T: Handler> Handler for MethodOverrideHandler<T> {
+    fn handle(self, mut state: State) -> Pin<Box<HandlerFuture>> {
+        match override_state_method(&self.options.0, &mut state) {
+            Ok(()) => self.inner_handler.handle(state),
+            Err(reason) => reject(&self.options.0, state, reason),
+        }
+    }
+}
+
+/// A Gotham middleware that overrides the method of requests passing through
+/// the pipeline it is part of.
+#[derive(Debug, Clone, Default)]
+pub struct MethodOverride {
+    options: SharedOptions,
+}
+
+impl MethodOverride {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NewMiddleware for MethodOverride {
+    type Instance = Self;
+
+    fn new_middleware(&self) -> anyhow::Result<Self::Instance> {
+        Ok(self.clone())
+    }
+}
+
+impl Middleware for MethodOverride {
+    fn call<Chain>(self, mut state: State, chain: Chain) -> Pin<Box<HandlerFuture>>
+    where
+        Chain: FnOnce(State) -> Pin<Box<HandlerFuture>> + Send + 'static,
+    {
+        match override_state_method(&self.options.0, &mut state) {
+            Ok(()) => chain(state),
+            Err(reason) => reject(&self.options.0, state, reason),
+        }
+    }
+}
+
+impl Builder {
+    /// Wraps a Gotham handler, typically the application's router, in a
+    /// `MethodOverrideHandler` with this configuration.
+    ///
+    /// Panics if `form_body` is enabled or a body policy is
+    /// `BodyPolicy::Strip`, as Gotham handlers are given the request's state
+    /// rather than the request.
+    pub fn build_gotham<T>(self, inner_handler: T) -> MethodOverrideHandler<T> {
+        self.assert_no_body("which the gotham adapters can't do");
+        MethodOverrideHandler {
+            inner_handler,
+            options: SharedOptions(Arc::new(self.into_options())),
+        }
+    }
+
+    /// Returns a Gotham `MethodOverride` middleware with this configuration.
+    ///
+    /// Panics if `form_body` is enabled or a body policy is
+    /// `BodyPolicy::Strip`, the same as `build_gotham`.
+    pub fn gotham_middleware(self) -> MethodOverride {
+        self.assert_no_body("which the gotham adapters can't do");
+        MethodOverride {
+            options: SharedOptions(Arc::new(self.into_options())),
+        }
+    }
+}
+
+// Gotham needs its handlers and middleware to be unwind safe, which the hooks
+// the options hold aren't required to be. The options are never changed once
+// built, so a panic can't leave them half updated.
+#[derive(Debug, Clone, Default)]
+struct SharedOptions(Arc<Options>);
+
+impl UnwindSafe for SharedOptions {}
+impl RefUnwindSafe for SharedOptions {}
+
+impl Head for State {
+    fn method_name(&self) -> &str {
+        Method::borrow_from(self).as_str()
+    }
+
+    fn path(&self) -> &str {
+        Uri::borrow_from(self).path()
+    }
+
+    fn query(&self) -> Option<&str> {
+        Uri::borrow_from(self).query()
+    }
+
+    fn header(&self, name: &str) -> Option<&[u8]> {
+        HeaderMap::borrow_from(self)
+            .get(name)
+            .map(HeaderValue::as_bytes)
+    }
+
+    fn has_cookie(&self, name: &str) -> bool {
+        let values = HeaderMap::borrow_from(self).get_all(COOKIE);
+        has_cookie(values.iter().map(HeaderValue::as_bytes), name)
+    }
+}
+
+impl StateData for MethodOverrideInfo {}
+
+/// Overrides the method in the state the same way the middleware overrides a
+/// request's, putting a `MethodOverrideInfo` in the state and removing the
+/// parameter from its `Uri`.
+fn override_state_method(options: &Options, state: &mut State) -> Result<(), RejectReason> {
+    let (new_method, info) = match options.override_method(state)? {
+        Some(new_method) => new_method,
+        None => return Ok(()),
+    };
+    *Method::borrow_mut_from(state) = new_method;
+    if options.should_strip(info.source) {
+        strip_param(Uri::borrow_mut_from(state), &options.param_name);
+    }
+    state.put(info);
+    Ok(())
+}
+
+/// Responds to a rejected request without calling the handler, the same way
+/// the middleware does.
+fn reject(options: &Options, state: State, reason: RejectReason) -> Pin<Box<HandlerFuture>> {
+    let mut res = create_empty_response(&state, reason.status());
+    for (name, value) in options.reject_headers(reason.status()) {
+        res.headers_mut().insert(name, value);
+    }
+    res.extensions_mut().insert(reason);
+    Box::pin(std::future::ready(Ok((state, res))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BodyPolicy, FORM_CONTENT_TYPE};
+    use ::gotham::pipeline::new_pipeline;
+    use ::gotham::pipeline::single::single_pipeline;
+    use ::gotham::router::builder::{
+        build_router, build_simple_router, DefineSingleRoute, DrawRoutes,
+    };
+    use ::gotham::router::Router;
+    use ::gotham::test::TestServer;
+    use http::header::{ALLOW, CONTENT_LENGTH, CONTENT_TYPE};
+    use http::StatusCode;
+    use std::panic::UnwindSafe;
+
+    fn handle(state: State) -> (State, String) {
+        let body = format!("{:?}", Method::borrow_from(&state));
+        (state, body)
+    }
+
+    fn describe(state: State) -> (State, String) {
+        let body = format!(
+            "{:?} {} {:?}",
+            Method::borrow_from(&state),
+            Uri::borrow_from(&state),
+            MethodOverrideInfo::try_borrow_from(&state)
+        );
+        (state, body)
+    }
+
+    fn send<T>(handler: T, method: Method, url: &str) -> String
+    where
+        T: NewHandler + 'static,
+        T::Instance: UnwindSafe,
+    {
+        let server = TestServer::new(handler).unwrap();
+        let client = server.client();
+        let request = client
+            .build_request(method, url)
+            .with_header(CONTENT_TYPE, HeaderValue::from_static(FORM_CONTENT_TYPE));
+        client.perform(request).unwrap().read_utf8_body().unwrap()
+    }
+
+    /// Sends a form request, returning the response's status, `Allow` header,
+    /// and body.
+    fn send_form<T>(
+        handler: T,
+        url: &str,
+        body: &'static str,
+    ) -> (StatusCode, Option<String>, String)
+    where
+        T: NewHandler + 'static,
+        T::Instance: UnwindSafe,
+    {
+        let server = TestServer::new(handler).unwrap();
+        let client = server.client();
+        let mut request = client
+            .build_request(Method::POST, url)
+            .with_header(CONTENT_TYPE, HeaderValue::from_static(FORM_CONTENT_TYPE))
+            .with_header(CONTENT_LENGTH, HeaderValue::from(body.len()));
+        *request.body_mut() = body.into();
+        let res = client.perform(request).unwrap();
+        let status = res.status();
+        let allow = res
+            .headers()
+            .get(ALLOW)
+            .map(|allow| allow.to_str().unwrap().to_string());
+        (status, allow, res.read_utf8_body().unwrap())
+    }
+
+    #[test]
+    fn handler_test() {
+        let router = || {
+            build_simple_router(|route| {
+                route.delete("/").to(handle);
+                route.post("/").to(handle);
+            })
+        };
+        let send = |method, url| send(MethodOverrideHandler::new(router()), method, url);
+
+        assert_eq!(send(Method::POST, "http://localhost/"), "POST");
+        assert_eq!(
+            send(Method::POST, "http://localhost/?_method=DELETE"),
+            "DELETE"
+        );
+        assert_eq!(send(Method::POST, "http://localhost/?_method=GET"), "POST");
+    }
+
+    #[test]
+    fn middleware_test() {
+        let router = || -> Router {
+            let (chain, pipelines) =
+                single_pipeline(new_pipeline().add(MethodOverride::new()).build());
+            build_router(chain, pipelines, |route| {
+                route.post("/").to(handle);
+            })
+        };
+        let send = |method, url| send(router(), method, url);
+
+        assert_eq!(send(Method::POST, "http://localhost/"), "POST");
+        assert_eq!(send(Method::POST, "http://localhost/?_method=PUT"), "PUT");
+        assert_eq!(send(Method::GET, "http://localhost/?_method=PUT"), "");
+    }
+
+    #[test]
+    fn builder_test() {
+        let builder = || {
+            Builder::new()
+                .param_name("_override")
+                .allow_method(Method::OPTIONS)
+                .source_methods(["POST", "GET"])
+        };
+        let router = || {
+            build_simple_router(|route| {
+                route.get("/").to(handle);
+                route.post("/").to(handle);
+                route.options("/").to(handle);
+            })
+        };
+        let send_handler = |method, url| send(builder().build_gotham(router()), method, url);
+
+        assert_eq!(
+            send_handler(Method::POST, "http://localhost/?_override=OPTIONS"),
+            "OPTIONS"
+        );
+        assert_eq!(
+            send_handler(Method::GET, "http://localhost/?_override=OPTIONS"),
+            "OPTIONS"
+        );
+        assert_eq!(
+            send_handler(Method::POST, "http://localhost/?_method=OPTIONS"),
+            "POST"
+        );
+
+        let router = || -> Router {
+            let middleware = builder().gotham_middleware();
+            let (chain, pipelines) = single_pipeline(new_pipeline().add(middleware).build());
+            build_router(chain, pipelines, |route| {
+                route.post("/").to(handle);
+            })
+        };
+        let send_middleware = |method, url| send(router(), method, url);
+
+        assert_eq!(
+            send_middleware(Method::POST, "http://localhost/?_override=OPTIONS"),
+            "OPTIONS"
+        );
+        assert_eq!(
+            send_middleware(Method::POST, "http://localhost/?_method=PUT"),
+            "POST"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "which the gotham adapters can't do")]
+    fn form_body_test() {
+        Builder::new().form_body(true).gotham_middleware();
+    }
+
+    #[test]
+    fn state_test() {
+        let router = || {
+            build_simple_router(|route| {
+                route.post("/item").to(describe);
+                route.delete("/item").to(describe);
+            })
+        };
+        let send_handler =
+            |builder: Builder, url| send(builder.build_gotham(router()), Method::POST, url);
+
+        // The parameter is removed and the override recorded
+        assert_eq!(
+            send_handler(
+                Builder::new(),
+                "http://localhost/item?a=1&_method=DELETE&b=2"
+            ),
+            "DELETE /item?a=1&b=2 Some(MethodOverrideInfo { original: POST, source: QueryParam })"
+        );
+        assert_eq!(
+            send_handler(
+                Builder::new().strip_param(false),
+                "http://localhost/item?_method=DELETE"
+            ),
+            "DELETE /item?_method=DELETE Some(MethodOverrideInfo { original: POST, source: QueryParam })"
+        );
+        assert_eq!(
+            send_handler(Builder::new(), "http://localhost/item?a=1"),
+            "POST /item?a=1 None"
+        );
+
+        let router = || -> Router {
+            let (chain, pipelines) =
+                single_pipeline(new_pipeline().add(MethodOverride::new()).build());
+            build_router(chain, pipelines, |route| {
+                route.post("/item").to(describe);
+            })
+        };
+        assert_eq!(
+            send(router(), Method::POST, "http://localhost/item?_method=PUT"),
+            "PUT /item Some(MethodOverrideInfo { original: POST, source: QueryParam })"
+        );
+    }
+
+    #[test]
+    fn reject_test() {
+        let router = || {
+            build_simple_router(|route| {
+                route.post("/").to(handle);
+                route.put("/").to(handle);
+                route.delete("/").to(handle);
+            })
+        };
+        let builder = || {
+            Builder::new()
+                .strict(true)
+                .body_policy(Method::DELETE, BodyPolicy::Reject)
+        };
+        let ok = |body: &str| (StatusCode::OK, None, body.to_string());
+        let allow = Some("POST, PUT, PATCH, DELETE".to_string());
+
+        // Strict mode and body policies reject requests, without calling the
+        // handler
+        let send_handler = |url, body| send_form(builder().build_gotham(router()), url, body);
+        assert_eq!(
+            send_handler("http://localhost/?_method=TRACE", ""),
+            (
+                StatusCode::METHOD_NOT_ALLOWED,
+                allow.clone(),
+                "".to_string()
+            )
+        );
+        assert_eq!(
+            send_handler("http://localhost/?_method=P%20T", "").0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            send_handler("http://localhost/?_method=DELETE", "a=1").0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            send_handler("http://localhost/?_method=DELETE", ""),
+            ok("DELETE")
+        );
+        assert_eq!(
+            send_handler("http://localhost/?_method=PUT", "a=1"),
+            ok("PUT")
+        );
+
+        // The same goes for the middleware
+        let router = || -> Router {
+            let (chain, pipelines) =
+                single_pipeline(new_pipeline().add(builder().gotham_middleware()).build());
+            build_router(chain, pipelines, |route| {
+                route.post("/").to(handle);
+            })
+        };
+        let send_middleware = |url, body| send_form(router(), url, body);
+        assert_eq!(
+            send_middleware("http://localhost/?_method=TRACE", ""),
+            (StatusCode::METHOD_NOT_ALLOWED, allow, "".to_string())
+        );
+        assert_eq!(
+            send_middleware("http://localhost/?_method=DELETE", "a=1").0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(send_middleware("http://localhost/?a=1", ""), ok("POST"));
+    }
+}
@@ -0,0 +1,808 @@
+//! Method overriding for [hyper 1](https://docs.rs/hyper/1), whose `Service`
+//! trait is called through a shared reference and which uses version 1 of the
+//! `http` crate.
+//!
+//!    let service = hyper1::MethodOverride::new(service_fn(handle));
+//!    hyper::server::conn::http1::Builder::new()
+//!        .serve_connection(io, service)
+//!        .await?;
+//!
+//! It is also a tower `Service` when the inner service is one, so tower
+//! services of version 1 `http` requests, such as an axum router, can be
+//! wrapped and served with hyper-util's `TowerToHyperService`.
+//!
+//! The service is configured with the same `Builder` as the middleware, using
+//! `Builder::build_hyper1`. Reading the method from form bodies and stripping
+//! bodies both need a request body that can be replaced, which hyper's
+//! `Incoming` bodies can't be, so `build_hyper1` panics if `Builder::form_body`
+//! is enabled or a body policy is `BodyPolicy::Strip`. For request bodies that
+//! implement version 1 of `http-body`, `From` version 1 of `bytes`, and
+//! `Default`, as axum's `Body` does, `Builder::build_hyper1_with_body` builds a
+//! `hyper1::BodyMethodOverride` that can do both.
+//!
+
+use crate::{
+    has_cookie, multipart_boundary, strip_param, trace_body_error, Builder, Head, Options,
+    OverrideRequest, OverrideSource, OverrideUri, RejectReason, BODY_HEADERS,
+};
+use bytes1::{Buf, Bytes};
+use http1::header::{HeaderName, HeaderValue, COOKIE};
+use http1::request::Parts;
+use http1::{Method, Request, Response, StatusCode, Uri};
+use hyper1::service::Service;
+use pin_project_lite::pin_project;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// A hyper 1 `Service` that overrides the method of incoming requests before
+/// passing them to the inner service.
+#[derive(Debug, Clone)]
+pub struct MethodOverride<T> {
+    inner_service: T,
+    options: Arc<Options>,
+}
+
+/// A `hyper1::MethodOverride` that can also read the method from form bodies
+/// and strip the bodies of overridden requests, the same as
+/// `BodyMethodOverride`. The request body must implement version 1 of
+/// `http_body::Body`, `From<Bytes>`, and `Default`, and the inner service
+/// `Clone`, as it is only called once a form body has been read.
+#[derive(Debug, Clone)]
+pub struct BodyMethodOverride<T> {
+    inner_service: T,
+    options: Arc<Options>,
+}
+
+/// Added to the extensions of requests whose method has been overridden, the
+/// same as `crate::MethodOverrideInfo` but holding a version 1 `http::Method`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodOverrideInfo {
+    /// The method the request was sent with.
+    pub original: Method,
+    /// Where the new method was given.
+    pub source: OverrideSource,
+}
+
+impl<T> MethodOverride<T> {
+    pub fn new(inner_service: T) -> Self {
+        Builder::new().build_hyper1(inner_service)
+    }
+}
+
+impl Builder {
+    /// Wraps a hyper 1 service in a `hyper1::MethodOverride` with this
+    /// configuration.
+    ///
+    /// Panics if `form_body` is enabled or a body policy is
+    /// `BodyPolicy::Strip`, as hyper 1 request bodies can't be replaced.
+    pub fn build_hyper1<T>(self, inner_service: T) -> MethodOverride<T> {
+        self.assert_no_body("which hyper1::MethodOverride can't do, unlike build_hyper1_with_body");
+        MethodOverride {
+            inner_service,
+            options: Arc::new(self.into_options()),
+        }
+    }
+
+    /// Wraps a hyper 1 service in a `hyper1::BodyMethodOverride` with this
+    /// configuration, which `Builder::form_body` and `BodyPolicy::Strip` need.
+    pub fn build_hyper1_with_body<T>(self, inner_service: T) -> BodyMethodOverride<T> {
+        BodyMethodOverride {
+            inner_service,
+            options: Arc::new(self.into_options()),
+        }
+    }
+}
+
+impl<Body> Head for Request<Body> {
+    fn method_name(&self) -> &str {
+        self.method().as_str()
+    }
+
+    fn path(&self) -> &str {
+        self.uri().path()
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.uri().query()
+    }
+
+    fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers().get(name).map(HeaderValue::as_bytes)
+    }
+
+    fn has_cookie(&self, name: &str) -> bool {
+        let values = self.headers().get_all(COOKIE);
+        has_cookie(values.iter().map(HeaderValue::as_bytes), name)
+    }
+}
+
+impl<Body> OverrideRequest for Request<Body> {
+    fn set_method(&mut self, new_method: http::Method, source: OverrideSource) {
+        let new_method = convert(new_method.as_str(), Method::from_bytes);
+        let original = std::mem::replace(self.method_mut(), new_method);
+        self.extensions_mut()
+            .insert(MethodOverrideInfo { original, source });
+    }
+
+    fn override_info(&self) -> Option<crate::MethodOverrideInfo> {
+        let info = self.extensions().get::<MethodOverrideInfo>()?;
+        Some(crate::MethodOverrideInfo {
+            original: convert(info.original.as_str(), http::Method::from_bytes),
+            source: info.source,
+        })
+    }
+
+    fn strip_param(&mut self, name: &str) {
+        strip_param(self.uri_mut(), name);
+    }
+}
+
+impl<S, Body, ResBody> Service<Request<Body>> for MethodOverride<S>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn call(&self, req: Request<Body>) -> Self::Future {
+        dispatch(
+            &self.options,
+            req,
+            |_| (),
+            |req| self.inner_service.call(req),
+        )
+    }
+}
+
+impl<S, Body, ResBody> tower_service::Service<Request<Body>> for MethodOverride<S>
+where
+    S: tower_service::Service<Request<Body>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        tower_service::Service::poll_ready(&mut self.inner_service, cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let inner_service = &mut self.inner_service;
+        dispatch(
+            &self.options,
+            req,
+            |_| (),
+            |req| tower_service::Service::call(inner_service, req),
+        )
+    }
+}
+
+impl<S, Body, ResBody> Service<Request<Body>> for BodyMethodOverride<S>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>> + Clone,
+    Body: http_body1::Body + From<Bytes> + Default,
+    Body::Error: fmt::Display,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BodyResponseFuture<S, Body, S::Future>;
+
+    fn call(&self, mut req: Request<Body>) -> Self::Future {
+        let candidate = self.options.is_candidate(&req);
+        let overridden = match self.options.apply_override(&mut req, candidate) {
+            Ok(overridden) => overridden,
+            Err(reason) => return BodyResponseFuture::reject(reason, &self.options),
+        };
+
+        if !overridden && self.options.should_read_body(&req, candidate) {
+            let service = self.inner_service.clone();
+            let call = |service: &mut S, req| Service::call(&*service, req);
+            return BodyResponseFuture::buffer(service, call, self.options.clone(), req);
+        }
+
+        let hint = self.options.should_hint(overridden, candidate);
+        let future = finish(&self.options, req, overridden, hint, strip_body, |req| {
+            self.inner_service.call(req)
+        });
+        BodyResponseFuture::inner(future)
+    }
+}
+
+impl<S, Body, ResBody> tower_service::Service<Request<Body>> for BodyMethodOverride<S>
+where
+    S: tower_service::Service<Request<Body>, Response = Response<ResBody>> + Clone,
+    Body: http_body1::Body + From<Bytes> + Default,
+    Body::Error: fmt::Display,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BodyResponseFuture<S, Body, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        tower_service::Service::poll_ready(&mut self.inner_service, cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let candidate = self.options.is_candidate(&req);
+        let overridden = match self.options.apply_override(&mut req, candidate) {
+            Ok(overridden) => overridden,
+            Err(reason) => return BodyResponseFuture::reject(reason, &self.options),
+        };
+
+        if !overridden && self.options.should_read_body(&req, candidate) {
+            // The inner service can only be called once the body has been
+            // read, so the future takes the service that has been readied.
+            let clone = self.inner_service.clone();
+            let service = std::mem::replace(&mut self.inner_service, clone);
+            let call = |service: &mut S, req| tower_service::Service::call(service, req);
+            return BodyResponseFuture::buffer(service, call, self.options.clone(), req);
+        }
+
+        let hint = self.options.should_hint(overridden, candidate);
+        let inner_service = &mut self.inner_service;
+        let future = finish(&self.options, req, overridden, hint, strip_body, |req| {
+            tower_service::Service::call(inner_service, req)
+        });
+        BodyResponseFuture::inner(future)
+    }
+}
+
+/// Overrides the method of the request, then calls the inner service with it
+/// unless it is rejected.
+fn dispatch<Body, F>(
+    options: &Arc<Options>,
+    mut req: Request<Body>,
+    strip_body: fn(&mut Request<Body>),
+    call: impl FnOnce(Request<Body>) -> F,
+) -> ResponseFuture<F> {
+    let candidate = options.is_candidate(&req);
+    let overridden = match options.apply_override(&mut req, candidate) {
+        Ok(overridden) => overridden,
+        Err(reason) => return ResponseFuture::reject(reason, options),
+    };
+
+    let hint = options.should_hint(overridden, candidate);
+    finish(options, req, overridden, hint, strip_body, call)
+}
+
+/// Applies the options that depend on the final method of the request, then
+/// calls the inner service with it unless it is rejected.
+fn finish<Body, F>(
+    options: &Arc<Options>,
+    req: Request<Body>,
+    overridden: bool,
+    hint: bool,
+    strip_body: fn(&mut Request<Body>),
+    call: impl FnOnce(Request<Body>) -> F,
+) -> ResponseFuture<F> {
+    let deprecation = options.deprecation_header(&req);
+    match options.dispatch(req, overridden, strip_body, call) {
+        Ok(future) => ResponseFuture {
+            kind: Kind::Inner { future },
+            hint: if hint { Some(options.clone()) } else { None },
+            deprecation,
+        },
+        Err(reason) => ResponseFuture::reject(reason, options),
+    }
+}
+
+fn strip_body<Body: Default>(req: &mut Request<Body>) {
+    *req.body_mut() = Body::default();
+    let headers = req.headers_mut();
+    for name in &BODY_HEADERS {
+        headers.remove(*name);
+    }
+}
+
+impl OverrideUri for Uri {
+    fn path(&self) -> &str {
+        self.path()
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.query()
+    }
+
+    fn set_path_and_query(&mut self, path_and_query: String) {
+        let mut parts = self.clone().into_parts();
+        parts.path_and_query = path_and_query.parse().ok();
+        if let Ok(uri) = Uri::from_parts(parts) {
+            *self = uri;
+        }
+    }
+}
+
+/// Converts a method, status, or header value to the same one from the other
+/// version of the `http` crate, which both parse from the same bytes.
+fn convert<T, E: fmt::Debug>(value: impl AsRef<[u8]>, from_bytes: fn(&[u8]) -> Result<T, E>) -> T {
+    from_bytes(value.as_ref()).expect("values are the same in both http versions")
+}
+
+/// Adds headers built for version 0.2 `http` responses to a version 1 one.
+fn insert_headers<Body>(
+    res: &mut Response<Body>,
+    headers: impl IntoIterator<Item = (http::header::HeaderName, http::HeaderValue)>,
+) {
+    for (name, value) in headers {
+        res.headers_mut().insert(
+            convert(name.as_str(), HeaderName::from_bytes),
+            convert(value, HeaderValue::from_bytes),
+        );
+    }
+}
+
+pin_project! {
+    /// The future returned by `hyper1::MethodOverride`.
+    pub struct ResponseFuture<F> {
+        #[pin]
+        kind: Kind<F>,
+        hint: Option<Arc<Options>>,
+        deprecation: Option<(http::header::HeaderName, http::header::HeaderValue)>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<F> {
+        Inner {
+            #[pin]
+            future: F,
+        },
+        Reject {
+            reason: RejectReason,
+            options: Arc<Options>,
+        },
+    }
+}
+
+impl<F> ResponseFuture<F> {
+    fn reject(reason: RejectReason, options: &Arc<Options>) -> Self {
+        Self {
+            kind: Kind::Reject {
+                reason,
+                options: options.clone(),
+            },
+            hint: None,
+            deprecation: None,
+        }
+    }
+}
+
+impl<F, ResBody, Error> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<ResBody>, Error>>,
+    ResBody: Default,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = match this.kind.project() {
+            KindProj::Inner { future } => match future.poll(cx) {
+                Poll::Ready(Ok(res)) => res,
+                other => return other,
+            },
+            KindProj::Reject { reason, options } => {
+                let mut res = Response::new(ResBody::default());
+                let status = reason.status();
+                *res.status_mut() = convert(status.as_str(), StatusCode::from_bytes);
+                insert_headers(&mut res, options.reject_headers(status));
+                res.extensions_mut().insert(*reason);
+                res
+            }
+        };
+
+        if let Some(options) = this.hint {
+            let status = convert(res.status().as_str(), http::StatusCode::from_bytes);
+            insert_headers(&mut res, options.hint_header(status));
+        }
+        insert_headers(&mut res, this.deprecation.take());
+        Poll::Ready(Ok(res))
+    }
+}
+
+pin_project! {
+    /// The future returned by `hyper1::BodyMethodOverride`, which reads a form
+    /// body before calling the inner service when the method may be given in
+    /// it.
+    pub struct BodyResponseFuture<S, Body, F> {
+        #[pin]
+        kind: BodyKind<S, Body, F>,
+    }
+}
+
+pin_project! {
+    #[project = BodyKindProj]
+    enum BodyKind<S, Body, F> {
+        // Reading a form body to look for the parameter in, before the inner
+        // service can be called.
+        Buffering {
+            #[pin]
+            body: Body,
+            buffer: Vec<u8>,
+            // The boundary of a multipart body, rather than a URL-encoded one.
+            boundary: Option<String>,
+            parts: Option<Parts>,
+            service: Option<S>,
+            // Calls the service through whichever `Service` trait it was
+            // called with.
+            call: fn(&mut S, Request<Body>) -> F,
+            options: Arc<Options>,
+        },
+        Inner {
+            #[pin]
+            future: ResponseFuture<F>,
+        },
+    }
+}
+
+impl<S, Body, F> BodyResponseFuture<S, Body, F> {
+    fn inner(future: ResponseFuture<F>) -> Self {
+        Self {
+            kind: BodyKind::Inner { future },
+        }
+    }
+
+    fn reject(reason: RejectReason, options: &Arc<Options>) -> Self {
+        Self::inner(ResponseFuture::reject(reason, options))
+    }
+
+    fn buffer(
+        service: S,
+        call: fn(&mut S, Request<Body>) -> F,
+        options: Arc<Options>,
+        req: Request<Body>,
+    ) -> Self {
+        let boundary = multipart_boundary(&req);
+        let (parts, body) = req.into_parts();
+        Self {
+            kind: BodyKind::Buffering {
+                body,
+                buffer: Vec::new(),
+                boundary,
+                parts: Some(parts),
+                service: Some(service),
+                call,
+                options,
+            },
+        }
+    }
+}
+
+impl<S, Body, F, ResBody, Error> Future for BodyResponseFuture<S, Body, F>
+where
+    Body: http_body1::Body + From<Bytes> + Default,
+    Body::Error: fmt::Display,
+    F: Future<Output = Result<Response<ResBody>, Error>>,
+    ResBody: Default,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            let (mut body, buffer, boundary, parts, service, call, options) =
+                match self.as_mut().project().kind.project() {
+                    BodyKindProj::Buffering {
+                        body,
+                        buffer,
+                        boundary,
+                        parts,
+                        service,
+                        call,
+                        options,
+                    } => (body, buffer, boundary, parts, service, *call, options),
+                    BodyKindProj::Inner { future } => return future.poll(cx),
+                };
+
+            match body.as_mut().poll_frame(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(frame))) => {
+                    // Trailers aren't part of the form, and are dropped with
+                    // the body.
+                    if let Ok(mut chunk) = frame.into_data() {
+                        while chunk.has_remaining() {
+                            let bytes = chunk.chunk();
+                            buffer.extend_from_slice(bytes);
+                            let read = bytes.len();
+                            chunk.advance(read);
+                        }
+                    }
+                    if buffer.len() > options.body_limit(boundary.as_deref()) {
+                        let reason = RejectReason::BodyTooLarge;
+                        let future = reject_body(options, parts.as_ref().unwrap(), reason);
+                        self.set(Self::inner(future));
+                    }
+                    continue;
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    let parts = parts.as_ref().unwrap();
+                    trace_body_error(parts.uri.path(), &error);
+                    let future = reject_body(options, parts, RejectReason::BodyUnreadable);
+                    self.set(Self::inner(future));
+                    continue;
+                }
+                Poll::Ready(None) => (),
+            }
+
+            let body = Bytes::from(std::mem::take(buffer));
+            let mut req = Request::from_parts(parts.take().unwrap(), Body::from(body.clone()));
+            let overridden = match options.apply_body_override(&mut req, &body, boundary.as_deref())
+            {
+                Ok(overridden) => overridden,
+                Err(reason) => {
+                    let future = ResponseFuture::reject(reason, options);
+                    self.set(Self::inner(future));
+                    continue;
+                }
+            };
+
+            // Only candidate requests have their body read.
+            let hint = options.should_hint(overridden, true);
+            let mut service = service.take().unwrap();
+            let future = finish(options, req, overridden, hint, strip_body, |req| {
+                call(&mut service, req)
+            });
+            self.set(Self::inner(future));
+        }
+    }
+}
+
+/// Reports a request rejected while its form body was read, and returns the
+/// future of the response to it.
+fn reject_body<F>(
+    options: &Arc<Options>,
+    parts: &Parts,
+    reason: RejectReason,
+) -> ResponseFuture<F> {
+    let (method, path) = (parts.method.as_str(), parts.uri.path());
+    options.report_rejection(method, OverrideSource::FormBody, path, reason);
+    ResponseFuture::reject(reason, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BodyPolicy, MethodOverrideMiddleware, FORM_CONTENT_TYPE, SUPPORTED_HEADER};
+    use http1::header::ALLOW;
+    use hyper1::service::service_fn;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn handle(req: Request<String>) -> Result<Response<String>, Infallible> {
+        let info = req.extensions().get::<MethodOverrideInfo>().cloned();
+        let body = format!(
+            "{} {} {:?}",
+            req.method(),
+            req.uri(),
+            info.map(|i| i.source)
+        );
+        Ok(Response::new(body))
+    }
+
+    async fn send<S>(service: &S, req: Request<String>) -> (StatusCode, String)
+    where
+        S: Service<Request<String>, Response = Response<String>, Error = Infallible>,
+    {
+        let res = service.call(req).await.unwrap();
+        (res.status(), res.into_body())
+    }
+
+    fn post(uri: &str) -> Request<String> {
+        Request::post(uri)
+            .header("content-type", FORM_CONTENT_TYPE)
+            .body(String::new())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn service_test() {
+        let service = MethodOverride::new(service_fn(handle));
+        let ok = |body: &str| (StatusCode::OK, body.to_string());
+
+        assert_eq!(
+            send(&service, post("/item?a=1&_method=DELETE")).await,
+            ok("DELETE /item?a=1 Some(QueryParam)")
+        );
+        assert_eq!(
+            send(&service, post("/?_method=GET")).await,
+            ok("POST /?_method=GET None")
+        );
+        let get = Request::get("/?_method=PUT").body(String::new()).unwrap();
+        assert_eq!(send(&service, get).await, ok("GET /?_method=PUT None"));
+    }
+
+    #[tokio::test]
+    async fn builder_test() {
+        let overrides = Arc::new(AtomicUsize::new(0));
+        let counter = overrides.clone();
+        let service = MethodOverrideMiddleware::builder()
+            .header_override(true)
+            .strict(true)
+            .body_policy(Method::DELETE, BodyPolicy::Reject)
+            .on_override(move |info| {
+                assert_eq!(info.original, http::Method::POST);
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build_hyper1(service_fn(handle));
+
+        let req = Request::post("/")
+            .header("x-http-method-override", "PUT")
+            .body(String::new())
+            .unwrap();
+        assert_eq!(
+            send(&service, req).await,
+            (StatusCode::OK, "PUT / Some(Header)".to_string())
+        );
+
+        // Rejections use the version 1 types too
+        let res = service.call(post("/?_method=TRACE")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[ALLOW], "POST, PUT, PATCH, DELETE");
+        let req = Request::post("/?_method=DELETE")
+            .header("content-type", FORM_CONTENT_TYPE)
+            .header("content-length", "3")
+            .body("a=1".to_string())
+            .unwrap();
+        let (status, _) = send(&service, req).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Only the request that reached the service was reported
+        assert_eq!(overrides.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "hyper1::MethodOverride can't")]
+    fn form_body_test() {
+        MethodOverrideMiddleware::builder()
+            .form_body(true)
+            .build_hyper1(());
+    }
+
+    #[test]
+    #[should_panic(expected = "hyper1::MethodOverride can't")]
+    fn strip_body_test() {
+        MethodOverrideMiddleware::builder()
+            .body_policy(Method::DELETE, BodyPolicy::Strip)
+            .build_hyper1(());
+    }
+
+    #[tokio::test]
+    async fn body_test() {
+        use http_body_util::{BodyExt, Full};
+
+        async fn echo(req: Request<Full<Bytes>>) -> Result<Response<String>, Infallible> {
+            let method = req.method().clone();
+            let length = req.headers().contains_key("content-length");
+            let body = req.into_body().collect().await.unwrap().to_bytes();
+            Ok(Response::new(format!("{} {:?} {}", method, body, length)))
+        }
+
+        async fn send<S>(service: &S, body: &'static str) -> (StatusCode, String)
+        where
+            S: Service<Request<Full<Bytes>>, Response = Response<String>, Error = Infallible>,
+        {
+            let req = Request::post("/")
+                .header("content-type", FORM_CONTENT_TYPE)
+                .header("content-length", body.len())
+                .body(Full::new(Bytes::from(body)))
+                .unwrap();
+            let res = service.call(req).await.unwrap();
+            (res.status(), res.into_body())
+        }
+
+        let service = MethodOverrideMiddleware::builder()
+            .form_body(true)
+            .form_body_limit(32)
+            .body_policy(Method::DELETE, BodyPolicy::Strip)
+            .build_hyper1_with_body(service_fn(echo));
+        let ok = |body: &str| (StatusCode::OK, body.to_string());
+
+        // The parameter is read from the body, which is passed on unchanged
+        assert_eq!(
+            send(&service, "a=1&_method=PUT").await,
+            ok(r#"PUT b"a=1&_method=PUT" true"#)
+        );
+        assert_eq!(
+            send(&service, "_method=GET").await,
+            ok(r#"POST b"_method=GET" true"#)
+        );
+
+        // Bodies are stripped by the policy of the method read from them
+        assert_eq!(
+            send(&service, "_method=DELETE").await,
+            ok(r#"DELETE b"" false"#)
+        );
+
+        // Bodies longer than the limit aren't read
+        let long = "_method=PUT&a=11111111111111111111";
+        assert_eq!(
+            send(&service, long).await,
+            ok(&format!("POST {:?} true", Bytes::from(long)))
+        );
+    }
+
+    #[tokio::test]
+    async fn body_rejection_test() {
+        use http_body_util::Full;
+        use std::sync::Mutex;
+
+        async fn handle(_: Request<Full<Bytes>>) -> Result<Response<String>, Infallible> {
+            Ok(Response::new(String::new()))
+        }
+
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let seen = reasons.clone();
+        let service = MethodOverrideMiddleware::builder()
+            .form_body(true)
+            .form_body_limit(16)
+            .strict(true)
+            .on_reject(move |rejection| seen.lock().unwrap().push(rejection.reason))
+            .build_hyper1_with_body(service_fn(handle));
+        let send = |length: usize, body: &'static str| {
+            let req = Request::post("/")
+                .header("content-type", FORM_CONTENT_TYPE)
+                .header("content-length", length)
+                .body(Full::new(Bytes::from(body)))
+                .unwrap();
+            service.call(req)
+        };
+
+        // Rejections read from the body use the version 1 types, as the
+        // others do
+        let res = send(13, "_method=TRACE").await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[ALLOW], "POST, PUT, PATCH, DELETE");
+        let res = send(3, "_method=PUT&a=123456").await.unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let reason = res.extensions().get::<RejectReason>();
+        assert_eq!(reason, Some(&RejectReason::BodyTooLarge));
+        assert_eq!(
+            *reasons.lock().unwrap(),
+            vec![RejectReason::MethodNotAllowed, RejectReason::BodyTooLarge]
+        );
+    }
+
+    #[tokio::test]
+    async fn hint_supported_methods_test() {
+        async fn not_allowed(_: Request<String>) -> Result<Response<String>, Infallible> {
+            let mut res = Response::new(String::new());
+            *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+            Ok(res)
+        }
+
+        let service = MethodOverrideMiddleware::builder()
+            .strict(true)
+            .hint_supported_methods(true)
+            .build_hyper1(service_fn(not_allowed));
+        let res = service.call(post("/")).await.unwrap();
+        assert_eq!(res.headers()[SUPPORTED_HEADER], "PUT, PATCH, DELETE");
+        assert!(!res.headers().contains_key(ALLOW));
+
+        // The service's own 405s get the hint, and an Allow header
+        let res = service.call(post("/?_method=TRACE")).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[SUPPORTED_HEADER], "PUT, PATCH, DELETE");
+        assert_eq!(res.headers()[ALLOW], "POST, PUT, PATCH, DELETE");
+    }
+
+    #[tokio::test]
+    async fn deprecation_header_test() {
+        let service = MethodOverrideMiddleware::builder()
+            .deprecate_source(OverrideSource::QueryParam)
+            .deprecation_header("@1767225600")
+            .build_hyper1(service_fn(handle));
+        let res = service.call(post("/?_method=PUT")).await.unwrap();
+        assert_eq!(res.headers()["deprecation"], "@1767225600");
+        assert_eq!(res.body(), "PUT / Some(QueryParam)");
+        let res = service.call(post("/")).await.unwrap();
+        assert!(!res.headers().contains_key("deprecation"));
+    }
+}
@@ -0,0 +1,96 @@
+//! A tower [`Layer`](tower::Layer) for adding the middleware to a
+//! `ServiceBuilder` stack, such as an axum application's:
+//!
+//!    let app = ServiceBuilder::new()
+//!        .layer(MethodOverrideLayer::new())
+//!        .service(app);
+//!
+
+use crate::{Builder, MethodOverrideMiddleware, Options};
+use std::sync::Arc;
+
+/// Wraps services in a `MethodOverrideMiddleware`. Created with the default
+/// configuration by `MethodOverrideLayer::new`, or from a configured builder
+/// with `Builder::layer`.
+#[derive(Debug, Clone, Default)]
+pub struct MethodOverrideLayer {
+    options: Arc<Options>,
+}
+
+impl MethodOverrideLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Builder {
+    /// Returns a layer that wraps services in a middleware with this
+    /// configuration.
+    ///
+    /// Panics if `form_body` is enabled or a body policy is
+    /// `BodyPolicy::Strip`, the same as `build`.
+    pub fn layer(self) -> MethodOverrideLayer {
+        self.assert_no_body("use Builder::build_with_body");
+        MethodOverrideLayer {
+            options: Arc::new(self.into_options()),
+        }
+    }
+}
+
+impl<S> tower::Layer<S> for MethodOverrideLayer {
+    type Service = MethodOverrideMiddleware<S>;
+
+    fn layer(&self, inner_service: S) -> Self::Service {
+        MethodOverrideMiddleware {
+            inner_service,
+            options: self.options.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FORM_CONTENT_TYPE;
+    use http::header::CONTENT_TYPE;
+    use http::Method;
+    use hyper::service::service_fn;
+    use hyper::{Body, Request, Response};
+    use std::convert::Infallible;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        Ok(Response::new(req.method().as_str().to_owned().into()))
+    }
+
+    async fn method(layer: MethodOverrideLayer, uri: &str) -> String {
+        let service = ServiceBuilder::new()
+            .layer(layer)
+            .service(service_fn(handle));
+        let req = Request::post(uri)
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .body(Body::empty())
+            .unwrap();
+        let res = service.oneshot(req).await.unwrap();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn layer_test() {
+        assert_eq!(
+            method(MethodOverrideLayer::new(), "/?_method=PUT").await,
+            "PUT"
+        );
+        assert_eq!(
+            method(MethodOverrideLayer::new(), "/?_method=GET").await,
+            "POST"
+        );
+
+        // Layers built from a builder use its configuration
+        let layer = MethodOverrideMiddleware::builder()
+            .allow_method(Method::OPTIONS)
+            .layer();
+        assert_eq!(method(layer, "/?_method=OPTIONS").await, "OPTIONS");
+    }
+}
@@ -3,8 +3,10 @@
 //! typically only support GET and POST requests, but our application may
 //! expect other HTTP methods that are more semantically correct.
 //!
-//! The methods PUT, PATCH, and DELETE are accepted for overriding, all others
-//! are ignored.
+//! The methods PUT, PATCH, and DELETE are accepted for overriding by default,
+//! all others are ignored. The parameter name, the methods that can be
+//! overridden to, and the methods of the requests that can be overridden are
+//! set with `MethodOverrideMiddleware::builder`.
 //!
 //! The `_method` query paramerter can be specified in a HTML form like so:
 //!
@@ -12,134 +14,2687 @@
 //!      <button type="submit">Delete item</button>
 //!    </form>
 //!
+//! With `Builder::form_body` enabled, and the middleware built with
+//! `Builder::build_with_body`, it can also be given in a hidden input of
+//! a URL-encoded form, or with the `multipart` feature a multipart one:
+//!
+//!    <form method="POST" action="/item/1">
+//!      <input type="hidden" name="_method" value="DELETE">
+//!      <button type="submit">Delete item</button>
+//!    </form>
+//!
+//! And with `Builder::header_override` enabled, in an `X-HTTP-Method-Override`
+//! or `X-HTTP-Method` header.
+//!
+
+#[cfg(feature = "axum")]
+pub mod axum;
+mod body;
+mod builder;
+mod future;
+#[cfg(feature = "gotham")]
+pub mod gotham;
+#[cfg(feature = "hyper-1")]
+pub mod hyper1;
+#[cfg(feature = "tower")]
+mod layer;
+#[cfg(feature = "multipart")]
+mod multipart;
+#[cfg(feature = "utoipa")]
+mod openapi;
+#[cfg(feature = "tiny_http")]
+pub mod tiny_http;
+#[cfg(feature = "tower-http")]
+mod trace;
+
+pub use body::{BodyMethodOverride, BodyResponseFuture};
+pub use builder::Builder;
+pub use future::ResponseFuture;
+#[cfg(feature = "tower")]
+pub use layer::MethodOverrideLayer;
+#[cfg(feature = "utoipa")]
+pub use openapi::{ConfiguredMethodOverrideModifier, MethodOverrideModifier};
+#[cfg(feature = "tower-http")]
+pub use trace::MethodOverrideMakeSpan;
+
+use http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, TRANSFER_ENCODING};
+use http::{Method, Request, StatusCode, Uri};
+use percent_encoding::percent_decode;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+#[cfg(feature = "tower")]
+use tower::util::BoxCloneService;
+use tower_service::Service;
+
+pub(crate) const SUPPORTED_HEADER: &str = "x-method-override-supported";
+const DEPRECATION_HEADER: &str = "deprecation";
+
+const DEFAULT_PARAM_NAME: &str = "_method";
+const DEFAULT_ALLOWED_METHODS: &[Method] = &[Method::PUT, Method::PATCH, Method::DELETE];
+const DEFAULT_SOURCE_METHODS: &[Method] = &[Method::POST];
+
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+const DEFAULT_CONTENT_TYPES: &[Cow<'static, str>] = &[
+    Cow::Borrowed(FORM_CONTENT_TYPE),
+    Cow::Borrowed("multipart/form-data"),
+];
+
+/// The headers read by `Builder::header_override`, in the order they are
+/// checked.
+const OVERRIDE_HEADERS: &[&str] = &["x-http-method-override", "x-http-method"];
+
+/// The `X-Requested-With` value sent by JavaScript clients such as jQuery,
+/// required by `Builder::require_requested_with`.
+const DEFAULT_REQUESTED_WITH: &str = "XMLHttpRequest";
+
+/// What to do with the body of a POST request once it has been overridden to
+/// a method that shouldn't have one, such as DELETE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyPolicy {
+    /// Pass the body on to the inner service unchanged.
+    Keep,
+    /// Replace the body with an empty one and remove its content headers. Only
+    /// supported by `BodyMethodOverride`.
+    Strip,
+    /// Respond with 400 Bad Request if the request has a body, without calling
+    /// the inner service.
+    Reject,
+}
+
+/// Which is used when a request gives a method in both an override header and
+/// the `_method` query parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precedence {
+    /// The query parameter is used. This is the default.
+    Query,
+    /// The header is used.
+    Header,
+}
+
+/// A baseline configuration to start a `Builder` from with
+/// `Builder::from_preset`, before tweaking it further. Those reading form
+/// bodies need the middleware to be built with `build_with_body`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// The `_method` field of form bodies and the override headers, with the
+    /// methods Rack's `MethodOverride` allows Rails to override to, less GET
+    /// and HEAD: PUT, PATCH, DELETE, OPTIONS, LINK, and UNLINK.
+    RailsCompatible,
+    /// The override headers, as Express's `method-override` reads them by
+    /// default, and the query parameter whatever the content type.
+    ExpressCompatible,
+    /// The `_method` field of form bodies, the override headers, and the query
+    /// parameter whatever the content type, with OPTIONS allowed too, as
+    /// Symfony reads them for Laravel.
+    LaravelCompatible,
+    /// The query parameter of form submissions only, rejecting requests that
+    /// give a method that can't be used. Best combined with
+    /// `Builder::require_cookie` naming the session cookie.
+    StrictSecure,
+    /// Every source, whatever the content type, with the methods of
+    /// `RailsCompatible` allowed.
+    Permissive,
+}
+
+/// Added to the extensions of requests whose method has been overridden, so
+/// services can tell a tunnelled request from one sent with that method, for
+/// CSRF checks or logging.
+///
+///    match req.extensions().get::<MethodOverrideInfo>() {
+///        Some(info) => assert_eq!(info.original, Method::POST),
+///        None => (), // The method was not overridden
+///    }
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodOverrideInfo {
+    /// The method the request was sent with.
+    pub original: Method,
+    /// Where the new method was given.
+    pub source: OverrideSource,
+}
+
+/// Where in a request the method it was overridden to was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideSource {
+    /// The `_method` query parameter.
+    QueryParam,
+    /// An `X-HTTP-Method-Override` or `X-HTTP-Method` header.
+    Header,
+    /// The `_method` field of a URL-encoded form body, or with the `multipart`
+    /// feature of a `multipart/form-data` one.
+    FormBody,
+}
+
+/// Why a request was rejected for the method it was to be overridden to, in
+/// strict mode or by `BodyPolicy::Reject`, or while its form body was read to
+/// look for the method in.
+///
+/// The middleware's rejection responses have an empty body, and this in their
+/// extensions, so a layer around the middleware can tell them from the inner
+/// service's responses and render a body for them, such as one negotiated
+/// from the request's `Accept` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// The value has a `%` not followed by two hex digits, or decodes to
+    /// invalid UTF-8.
+    MalformedEncoding,
+    /// The value isn't a method.
+    NotAMethod,
+    /// The value is a method that can't be overridden to.
+    MethodNotAllowed,
+    /// The request has a body, and is overridden to a method whose body
+    /// policy is `BodyPolicy::Reject`.
+    BodyNotAllowed,
+    /// The form body turned out longer than `Builder::form_body_limit`, or
+    /// `Builder::multipart_body_limit` for a multipart one, though its
+    /// `Content-Length` was within it.
+    BodyTooLarge,
+    /// The form body couldn't be read.
+    BodyUnreadable,
+}
+
+impl RejectReason {
+    /// The status of the response the request is rejected with.
+    pub fn status(self) -> StatusCode {
+        match self {
+            RejectReason::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            RejectReason::BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Given to the `Builder::on_reject` hook for each request rejected for the
+/// method it was to be overridden to, and for each request whose method
+/// couldn't be used outside strict mode, which is passed through instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodOverrideRejection {
+    /// The method the request was sent with.
+    pub original: Method,
+    /// Where the rejected method was given.
+    pub source: OverrideSource,
+    pub reason: RejectReason,
+    /// Whether the request was passed on to the inner service with its
+    /// original method rather than rejected, as it is outside strict mode.
+    pub passed_through: bool,
+}
+
+/// The largest form body that is read to look for the `_method` parameter, by
+/// default.
+pub const DEFAULT_FORM_BODY_LIMIT: usize = 64 * 1024;
+
+/// The largest `multipart/form-data` body that is read to look for the
+/// `_method` field, by default. Larger than `DEFAULT_FORM_BODY_LIMIT` so that
+/// forms with file uploads can use a hidden input.
+pub const DEFAULT_MULTIPART_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct MethodOverrideMiddleware<T> {
+    inner_service: T,
+    options: Arc<Options>,
+}
+
+/// The method given in one of a request's sources: `None` if it gives none,
+/// or why to reject the request in strict mode if the method can't be
+/// overridden to.
+pub(crate) type Lookup = Option<Result<Method, RejectReason>>;
+
+/// The callback given to `Builder::on_override`.
+#[derive(Clone)]
+pub(crate) struct OverrideHook(pub(crate) Arc<dyn Fn(&MethodOverrideInfo) + Send + Sync>);
+
+impl fmt::Debug for OverrideHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OverrideHook")
+    }
+}
+
+/// The callback given to `Builder::on_reject`.
+#[derive(Clone)]
+pub(crate) struct RejectHook(pub(crate) Arc<dyn Fn(&MethodOverrideRejection) + Send + Sync>);
+
+impl fmt::Debug for RejectHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RejectHook")
+    }
+}
+
+/// The predicate given to `Builder::filter`.
+#[derive(Clone)]
+pub(crate) struct RequestFilter(pub(crate) Arc<dyn Fn(&RequestHead<'_>) -> bool + Send + Sync>);
+
+impl fmt::Debug for RequestFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RequestFilter")
+    }
+}
+
+type ResolveFn = dyn Fn(&str) -> Option<Method> + Send + Sync;
+
+/// The function given to `Builder::method_resolver`.
+#[derive(Clone)]
+pub(crate) struct MethodResolver(pub(crate) Arc<ResolveFn>);
+
+impl fmt::Debug for MethodResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MethodResolver")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Options {
+    param_name: Cow<'static, str>,
+    allowed_methods: Cow<'static, [Method]>,
+    source_methods: Cow<'static, [Method]>,
+    header_override: bool,
+    precedence: Precedence,
+    method_resolver: Option<MethodResolver>,
+    check_content_type: bool,
+    content_types: Cow<'static, [Cow<'static, str>]>,
+    strict: bool,
+    strip_param: bool,
+    pub(crate) hint_supported_methods: bool,
+    required_cookie: Option<String>,
+    requested_with: Option<Cow<'static, str>>,
+    deprecated_sources: Vec<OverrideSource>,
+    deprecation_header: Option<HeaderValue>,
+    include_prefixes: Vec<String>,
+    exclude_prefixes: Vec<String>,
+    filter: Option<RequestFilter>,
+    body_policies: Vec<(Method, BodyPolicy)>,
+    form_body: bool,
+    pub(crate) form_body_limit: usize,
+    pub(crate) multipart_body_limit: usize,
+    on_override: Option<OverrideHook>,
+    on_reject: Option<RejectHook>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            param_name: Cow::Borrowed(DEFAULT_PARAM_NAME),
+            allowed_methods: Cow::Borrowed(DEFAULT_ALLOWED_METHODS),
+            source_methods: Cow::Borrowed(DEFAULT_SOURCE_METHODS),
+            header_override: false,
+            precedence: Precedence::Query,
+            method_resolver: None,
+            check_content_type: true,
+            content_types: Cow::Borrowed(DEFAULT_CONTENT_TYPES),
+            strict: false,
+            strip_param: true,
+            hint_supported_methods: false,
+            required_cookie: None,
+            requested_with: None,
+            deprecated_sources: Vec::new(),
+            deprecation_header: None,
+            include_prefixes: Vec::new(),
+            exclude_prefixes: Vec::new(),
+            filter: None,
+            body_policies: Vec::new(),
+            form_body: false,
+            form_body_limit: DEFAULT_FORM_BODY_LIMIT,
+            multipart_body_limit: DEFAULT_MULTIPART_BODY_LIMIT,
+            on_override: None,
+            on_reject: None,
+        }
+    }
+}
+
+impl MethodOverrideMiddleware<()> {
+    /// Returns a `Builder` for configuring the middleware.
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
+}
+
+impl<T> MethodOverrideMiddleware<T> {
+    pub fn new(inner_service: T) -> Self {
+        Builder::new().build(inner_service)
+    }
+}
+
+impl Options {
+    /// Overrides the method of the request in place, returning whether it was
+    /// changed, or in strict mode why to reject the request.
+    pub(crate) fn apply_override(
+        &self,
+        req: &mut impl OverrideRequest,
+        candidate: bool,
+    ) -> Result<bool, RejectReason> {
+        if !self.eligible(req, candidate) {
+            return Ok(false);
+        }
+
+        match self.resolve(req)? {
+            Some((new_method, source)) => {
+                req.set_method(new_method, source);
+                if self.should_strip(source) {
+                    req.strip_param(&self.param_name);
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Overrides the method of the request in place from the form body read
+    /// from it, returning whether it was changed, or in strict mode why to
+    /// reject the request.
+    pub(crate) fn apply_body_override(
+        &self,
+        req: &mut impl OverrideRequest,
+        body: &[u8],
+        boundary: Option<&str>,
+    ) -> Result<bool, RejectReason> {
+        let lookup = self.body_lookup(body, boundary);
+        let source = OverrideSource::FormBody;
+        match self.check(lookup, req.method_name(), source, req.path())? {
+            Some(new_method) => {
+                req.set_method(new_method, source);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Returns whether the request is of a source method, and its path and the
+    /// filter allow it to be overridden. This is worked out once per request,
+    /// so the filter is only called once, and passed to the checks that need
+    /// it.
+    pub(crate) fn is_candidate(&self, req: &impl Head) -> bool {
+        self.is_source(req.method_name()) && self.in_scope(req)
+    }
+
+    /// Returns whether a candidate request can be overridden at all, before
+    /// looking at what it was to be overridden to.
+    pub(crate) fn eligible(&self, req: &impl Head, candidate: bool) -> bool {
+        let has_cookie = match &self.required_cookie {
+            Some(name) => req.has_cookie(name),
+            None => true,
+        };
+        candidate && has_cookie
+    }
+
+    fn is_source(&self, method: &str) -> bool {
+        self.source_methods
+            .iter()
+            .any(|source| source.as_str() == method)
+    }
+
+    /// Returns whether the request's path and the filter allow it to be
+    /// overridden.
+    fn in_scope(&self, req: &impl Head) -> bool {
+        let path = normalize_path(req.path());
+        let path = path.as_ref();
+        let included = self.include_prefixes.is_empty()
+            || self
+                .include_prefixes
+                .iter()
+                .any(|prefix| has_prefix(path, prefix));
+        let excluded = self
+            .exclude_prefixes
+            .iter()
+            .any(|prefix| has_prefix(path, prefix));
+        let filtered = match &self.filter {
+            Some(RequestFilter(filter)) => filter(&RequestHead { head: req }),
+            None => true,
+        };
+        included && !excluded && filtered
+    }
+
+    /// Returns whether a 405 response to a candidate request that wasn't
+    /// overridden should get the `X-Method-Override-Supported` header.
+    pub(crate) fn should_hint(&self, overridden: bool, candidate: bool) -> bool {
+        self.hint_supported_methods && !overridden && candidate
+    }
+
+    /// Returns the method to override an eligible request to from its query or
+    /// headers, and where it was given.
+    pub(crate) fn resolve(
+        &self,
+        req: &impl Head,
+    ) -> Result<Option<(Method, OverrideSource)>, RejectReason> {
+        let query = if self.content_type_allowed(req) {
+            self.param_lookup(req.query().unwrap_or("").as_bytes())
+        } else {
+            None
+        };
+        let query = (query, OverrideSource::QueryParam);
+        let header = (self.header_lookup(req), OverrideSource::Header);
+        let mut lookups = match self.precedence {
+            Precedence::Query => [query, header],
+            Precedence::Header => [header, query],
+        };
+
+        for (lookup, source) in lookups.iter_mut() {
+            let checked = self.check(lookup.take(), req.method_name(), *source, req.path());
+            if let Some(new_method) = checked? {
+                return Ok(Some((new_method, *source)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the method from a lookup, if there is one to override to. Values
+    /// that can't be used are ignored, unless in strict mode, and reported
+    /// either way.
+    pub(crate) fn check(
+        &self,
+        lookup: Lookup,
+        original: &str,
+        source: OverrideSource,
+        path: &str,
+    ) -> Result<Option<Method>, RejectReason> {
+        match lookup {
+            Some(Ok(method)) => Ok(Some(method)),
+            Some(Err(reason)) if self.strict => {
+                self.report_rejection(original, source, path, reason);
+                Err(reason)
+            }
+            Some(Err(reason)) => {
+                self.report(original, source, path, reason, true);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the method to override the request to, and the info about the
+    /// override, for adapters that don't have an `http::Request` to change, or
+    /// why the adapter should reject the request.
+    #[cfg(any(feature = "gotham", feature = "tiny_http"))]
+    pub(crate) fn override_method(
+        &self,
+        req: &impl Head,
+    ) -> Result<Option<(Method, MethodOverrideInfo)>, RejectReason> {
+        if !self.eligible(req, self.is_candidate(req)) {
+            return Ok(None);
+        }
+
+        let (new_method, source) = match self.resolve(req)? {
+            Some(resolved) => resolved,
+            None => return Ok(None),
+        };
+        if self.body_policy_for(&new_method) == BodyPolicy::Reject && has_body(req) {
+            let reason = RejectReason::BodyNotAllowed;
+            self.report_rejection(req.method_name(), source, req.path(), reason);
+            return Err(reason);
+        }
+
+        let original = Method::from_bytes(req.method_name().as_bytes())
+            .expect("the request's method is a valid method");
+        let info = MethodOverrideInfo { original, source };
+        self.report_override(&info, &new_method, req.path());
+        Ok(Some((new_method, info)))
+    }
+
+    /// Returns whether the parameter should be removed from the URI of a
+    /// request overridden with the method from the given source.
+    pub(crate) fn should_strip(&self, source: OverrideSource) -> bool {
+        source == OverrideSource::QueryParam && self.strip_param
+    }
+
+    /// Returns whether the request's content type allows the query parameter
+    /// to be used.
+    fn content_type_allowed(&self, req: &impl Head) -> bool {
+        if !self.check_content_type {
+            return true;
+        }
+
+        match content_type(req) {
+            Some(content_type) => self
+                .content_types
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(content_type)),
+            None => false,
+        }
+    }
+
+    /// Looks up the method given in the parameter of a query string or form
+    /// body.
+    ///
+    /// Rather than guessing at what a malformed value was meant to be, one with
+    /// a `%` not followed by two hex digits, or that decodes to invalid UTF-8,
+    /// is treated as a bad request.
+    pub(crate) fn param_lookup(&self, params: &[u8]) -> Lookup {
+        if !may_contain_param(params, &self.param_name) {
+            return None;
+        }
+
+        let value = query_param(params, &self.param_name)?;
+        Some(match decode_component(value) {
+            Some(value) => self.allowed_method(value.as_bytes()),
+            None => Err(RejectReason::MalformedEncoding),
+        })
+    }
+
+    /// Looks up the method given in a form body, which is multipart when it has
+    /// a boundary and URL-encoded otherwise. Multipart values aren't percent
+    /// encoded.
+    pub(crate) fn body_lookup(&self, body: &[u8], boundary: Option<&str>) -> Lookup {
+        match boundary {
+            #[cfg(feature = "multipart")]
+            Some(boundary) => {
+                let value = multipart::field(body, boundary, &self.param_name)?;
+                Some(self.allowed_method(value))
+            }
+            _ => self.param_lookup(body),
+        }
+    }
+
+    /// Looks up the method given in the first override header of the request,
+    /// if header overrides are enabled and the request has any `X-Requested-With`
+    /// header they require.
+    fn header_lookup(&self, req: &impl Head) -> Lookup {
+        if !self.header_override {
+            return None;
+        }
+        if let Some(expected) = &self.requested_with {
+            let value = req.header("x-requested-with")?;
+            if !value.trim_ascii().eq_ignore_ascii_case(expected.as_bytes()) {
+                return None;
+            }
+        }
+
+        let value = OVERRIDE_HEADERS.iter().find_map(|name| req.header(name))?;
+        Some(self.allowed_method(value))
+    }
+
+    /// Returns the method a value names, if it can be overridden to. Values
+    /// are case-insensitive, so `delete` gives DELETE, unless a resolver has
+    /// been given. They are compared with the allowed methods in place, without
+    /// allocating.
+    fn allowed_method(&self, value: &[u8]) -> Result<Method, RejectReason> {
+        if let Some(MethodResolver(resolve)) = &self.method_resolver {
+            let value = std::str::from_utf8(value).map_err(|_| RejectReason::NotAMethod)?;
+            let method = resolve(value).ok_or(RejectReason::NotAMethod)?;
+            return if self.allowed_methods.contains(&method) {
+                Ok(method)
+            } else {
+                Err(RejectReason::MethodNotAllowed)
+            };
+        }
+
+        let allowed = self
+            .allowed_methods
+            .iter()
+            .find(|method| method.as_str().as_bytes().eq_ignore_ascii_case(value));
+        match allowed {
+            Some(method) => Ok(method.clone()),
+            None if is_token(value) => Err(RejectReason::MethodNotAllowed),
+            None => Err(RejectReason::NotAMethod),
+        }
+    }
+
+    /// The value of the `Allow` header of the middleware's own 405 responses,
+    /// which lists the methods the request may be sent with and those it may
+    /// be overridden to, as the methods the inner service supports aren't
+    /// known.
+    pub(crate) fn allow(&self) -> HeaderValue {
+        let mut names: Vec<&str> = Vec::new();
+        for method in self
+            .source_methods
+            .iter()
+            .chain(self.allowed_methods.iter())
+        {
+            if !names.contains(&method.as_str()) {
+                names.push(method.as_str());
+            }
+        }
+        HeaderValue::from_str(&names.join(", ")).expect("method names are valid header values")
+    }
+
+    /// The value of the `X-Method-Override-Supported` header.
+    pub(crate) fn supported_methods(&self) -> HeaderValue {
+        let names: Vec<&str> = self.allowed_methods.iter().map(Method::as_str).collect();
+        HeaderValue::from_str(&names.join(", ")).expect("method names are valid header values")
+    }
+
+    pub(crate) fn body_policy_for(&self, method: &Method) -> BodyPolicy {
+        self.body_policies
+            .iter()
+            .find(|(existing, _)| existing == method)
+            .map_or(BodyPolicy::Keep, |(_, policy)| *policy)
+    }
+
+    /// Checks an overridden request against the body policy of its new method,
+    /// then reports the override, or the rejection if its body isn't allowed.
+    pub(crate) fn accept_override(&self, req: &impl OverrideRequest) -> Result<(), RejectReason> {
+        let info = match req.override_info() {
+            Some(info) => info,
+            None => return Ok(()),
+        };
+        let (method, path) = (method_of(req), req.path());
+        if self.body_policy_for(&method) == BodyPolicy::Reject && has_body(req) {
+            let reason = RejectReason::BodyNotAllowed;
+            self.report_rejection(info.original.as_str(), info.source, path, reason);
+            return Err(reason);
+        }
+        self.report_override(&info, &method, path);
+        Ok(())
+    }
+
+    /// Applies the options that depend on the final method of the request,
+    /// then calls the inner service with it, unless it is rejected.
+    pub(crate) fn dispatch<R: OverrideRequest, F>(
+        &self,
+        mut req: R,
+        overridden: bool,
+        strip_body: fn(&mut R),
+        call: impl FnOnce(R) -> F,
+    ) -> Result<F, RejectReason> {
+        if overridden {
+            self.accept_override(&req)?;
+            if self.body_policy_for(&method_of(&req)) == BodyPolicy::Strip {
+                strip_body(&mut req);
+            }
+        }
+        Ok(call(req))
+    }
+
+    /// Overrides the method of the request in place for adapters that don't
+    /// call a service, returning whether it was changed, or why the request
+    /// should be rejected. See `apply_override_sync` for how this differs from
+    /// the middleware.
+    pub(crate) fn apply_override_sync<Body>(
+        &self,
+        req: &mut Request<Body>,
+    ) -> Result<bool, RejectReason> {
+        let candidate = self.is_candidate(req);
+        if !self.apply_override(req, candidate)? {
+            return Ok(false);
+        }
+        self.accept_override(req)?;
+        Ok(true)
+    }
+
+    /// Reports an override that has been applied, as a tracing event, and a
+    /// warning if its source is deprecated, and to the `Builder::on_override`
+    /// hook.
+    pub(crate) fn report_override(&self, info: &MethodOverrideInfo, method: &Method, path: &str) {
+        trace_override(info, method, path);
+        if self.deprecated_sources.contains(&info.source) {
+            trace_deprecated(info, method, path);
+        }
+        if let Some(OverrideHook(hook)) = &self.on_override {
+            hook(info);
+        }
+    }
+
+    /// Reports a request rejected for the method it was to be overridden to,
+    /// as a tracing event and to the `Builder::on_reject` hook.
+    pub(crate) fn report_rejection(
+        &self,
+        original: &str,
+        source: OverrideSource,
+        path: &str,
+        reason: RejectReason,
+    ) {
+        self.report(original, source, path, reason, false);
+    }
+
+    fn report(
+        &self,
+        original: &str,
+        source: OverrideSource,
+        path: &str,
+        reason: RejectReason,
+        passed_through: bool,
+    ) {
+        if passed_through {
+            trace_ignored(original, source, path, reason);
+        } else {
+            trace_rejection(original, source, path, reason);
+        }
+        if let Some(RejectHook(hook)) = &self.on_reject {
+            let original = Method::from_bytes(original.as_bytes())
+                .expect("the request's method is a valid method");
+            hook(&MethodOverrideRejection {
+                original,
+                source,
+                reason,
+                passed_through,
+            });
+        }
+    }
+
+    /// Returns whether the middleware needs to be able to replace request
+    /// bodies, which only `BodyMethodOverride` can.
+    pub(crate) fn needs_body(&self) -> bool {
+        self.form_body
+            || self
+                .body_policies
+                .iter()
+                .any(|(_, policy)| *policy == BodyPolicy::Strip)
+    }
+
+    pub(crate) fn should_read_body(&self, req: &impl Head, candidate: bool) -> bool {
+        if !self.form_body || !self.eligible(req, candidate) {
+            return false;
+        }
+
+        let limit = if multipart_boundary(req).is_some() {
+            self.multipart_body_limit
+        } else {
+            match content_type(req) {
+                Some(content_type) if content_type.eq_ignore_ascii_case(FORM_CONTENT_TYPE) => {
+                    self.form_body_limit
+                }
+                _ => return false,
+            }
+        };
+        let length = req
+            .header(CONTENT_LENGTH.as_str())
+            .and_then(|length| std::str::from_utf8(length).ok())
+            .and_then(|length| length.parse::<usize>().ok());
+
+        matches!(length, Some(length) if length <= limit)
+    }
+
+    /// Returns the longest body that is read to look for the method in, which
+    /// is multipart when it has a boundary and URL-encoded otherwise.
+    pub(crate) fn body_limit(&self, boundary: Option<&str>) -> usize {
+        match boundary {
+            Some(_) => self.multipart_body_limit,
+            None => self.form_body_limit,
+        }
+    }
+}
+
+/// The middleware wrapping a boxed, cloneable inner service, for holding
+/// middleware around services of different types in the same place.
+#[cfg(feature = "tower")]
+pub type BoxedMethodOverride<Body, Response, Error> =
+    MethodOverrideMiddleware<BoxCloneService<Request<Body>, Response, Error>>;
+
+#[cfg(feature = "tower")]
+impl<Body, Response, Error> BoxedMethodOverride<Body, Response, Error> {
+    pub fn boxed<InnerService>(inner_service: InnerService) -> Self
+    where
+        InnerService:
+            Service<Request<Body>, Response = Response, Error = Error> + Clone + Send + 'static,
+        InnerService::Future: Send + 'static,
+    {
+        Self::new(BoxCloneService::new(inner_service))
+    }
+}
+
+impl<InnerService, Body, ResBody> Service<Request<Body>> for MethodOverrideMiddleware<InnerService>
+where
+    InnerService: Service<Request<Body>, Response = http::Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = InnerService::Response;
+    type Error = InnerService::Error;
+    type Future = ResponseFuture<InnerService::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner_service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let candidate = self.options.is_candidate(&req);
+        let overridden = match self.options.apply_override(&mut req, candidate) {
+            Ok(overridden) => overridden,
+            Err(reason) => return ResponseFuture::reject(reason, &self.options),
+        };
+
+        // `Builder::build` doesn't allow `BodyPolicy::Strip`, so there are no
+        // bodies to strip.
+        let hint = self.options.should_hint(overridden, candidate);
+        dispatch(
+            &mut self.inner_service,
+            &self.options,
+            req,
+            overridden,
+            hint,
+            |_| (),
+        )
+    }
+}
+
+/// Applies the options that depend on the final method of the request, then
+/// calls the inner service.
+pub(crate) fn dispatch<S, Body>(
+    service: &mut S,
+    options: &Arc<Options>,
+    req: Request<Body>,
+    overridden: bool,
+    hint: bool,
+    strip_body: fn(&mut Request<Body>),
+) -> ResponseFuture<S::Future>
+where
+    S: Service<Request<Body>>,
+{
+    let deprecation = options.deprecation_header(&req);
+    match options.dispatch(req, overridden, strip_body, |req| service.call(req)) {
+        Ok(future) => ResponseFuture::inner(
+            future,
+            if hint { Some(options.clone()) } else { None },
+            deprecation,
+        ),
+        Err(reason) => ResponseFuture::reject(reason, options),
+    }
+}
+
+pub(crate) fn has_body(req: &impl Head) -> bool {
+    let has_length = match req.header(CONTENT_LENGTH.as_str()) {
+        Some(length) => length != b"0",
+        None => false,
+    };
+    has_length || req.header(TRANSFER_ENCODING.as_str()).is_some()
+}
+
+/// The headers describing a request body, removed with it when it is
+/// stripped.
+pub(crate) const BODY_HEADERS: [&str; 4] = [
+    "content-length",
+    "transfer-encoding",
+    "content-type",
+    "content-encoding",
+];
+
+pub(crate) fn strip_body<Body: Default>(req: &mut Request<Body>) {
+    *req.body_mut() = Body::default();
+    let headers = req.headers_mut();
+    for name in &BODY_HEADERS {
+        headers.remove(*name);
+    }
+}
+
+/// Overrides the method of the request in place, the same way the middleware
+/// does with its default configuration, for use by applications that aren't
+/// built on `Service`. Returns whether the method was changed, in which case a
+/// `MethodOverrideInfo` is added to the request's extensions. The parameter is
+/// removed from the URI, and the override is recorded with tracing, as they
+/// are by the middleware.
+///
+/// Unlike the middleware, it doesn't call anything afterwards, so it can't
+/// read the method from a form body, strip the body of the request, or
+/// respond to it.
+///
+/// `SyncMethodOverride` does the same with a configuration from a `Builder`.
+pub fn apply_override_sync<Body>(req: &mut Request<Body>) -> bool {
+    SyncMethodOverride::default().apply(req) == Ok(true)
+}
+
+/// Overrides the method of requests in place, as `apply_override_sync` does,
+/// with a configuration from `Builder::build_sync`.
+#[derive(Debug, Clone, Default)]
+pub struct SyncMethodOverride {
+    options: Arc<Options>,
+}
+
+impl SyncMethodOverride {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the method of the request in place, returning whether it was
+    /// changed, or in strict mode and for methods with `BodyPolicy::Reject`
+    /// why the application should reject the request. Rejected requests are
+    /// reported to `Builder::on_reject`, but responding to them is left to the
+    /// application.
+    pub fn apply<Body>(&self, req: &mut Request<Body>) -> Result<bool, RejectReason> {
+        self.options.apply_override_sync(req)
+    }
+}
+
+/// A view of the request given to `Builder::filter`, the same for each version
+/// of the `http` crate and each framework the middleware supports.
+pub struct RequestHead<'a> {
+    head: &'a dyn Head,
+}
+
+impl RequestHead<'_> {
+    /// The request's method, before it is overridden.
+    pub fn method(&self) -> &str {
+        self.head.method_name()
+    }
+
+    pub fn path(&self) -> &str {
+        self.head.path()
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        self.head.query()
+    }
+
+    /// Returns the first value of the named header.
+    pub fn header(&self, name: &str) -> Option<&[u8]> {
+        self.head.header(&name.to_ascii_lowercase())
+    }
+}
+
+impl fmt::Debug for RequestHead<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestHead")
+            .field("method", &self.method())
+            .field("path", &self.path())
+            .field("query", &self.query())
+            .finish()
+    }
+}
+
+/// Returns the path as routers are likely to see it, so scoping by prefix
+/// can't be evaded by spelling the path differently: percent-encoded
+/// unreserved characters are decoded, repeated slashes collapsed, and `.` and
+/// `..` segments removed. Other percent encoding is kept, as decoding `%2F`
+/// would change which segments there are.
+fn normalize_path(path: &str) -> Cow<'_, str> {
+    let needs_normalizing = path.contains("//")
+        || path.contains('%')
+        || path
+            .split('/')
+            .any(|segment| segment == "." || segment == "..");
+    if !needs_normalizing {
+        return Cow::Borrowed(path);
+    }
+
+    let mut segments: Vec<String> = Vec::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        match decode_unreserved(segment).as_ref() {
+            "." => (),
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment.to_string()),
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    if path.ends_with('/') && !segments.is_empty() {
+        normalized.push('/');
+    }
+    Cow::Owned(normalized)
+}
+
+/// Decodes the percent-encoded unreserved characters of RFC 3986, which mean
+/// the same encoded or not.
+fn decode_unreserved(segment: &str) -> Cow<'_, str> {
+    if !segment.contains('%') {
+        return Cow::Borrowed(segment);
+    }
+
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(segment.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i + 1..i + 3) {
+            Some(digits) if bytes[i] == b'%' => std::str::from_utf8(digits)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .filter(|byte| byte.is_ascii_alphanumeric() || b"-._~".contains(byte)),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    Cow::Owned(String::from_utf8(decoded).expect("only ASCII characters are decoded"))
+}
+
+/// Returns whether the value is a token, as method names must be.
+fn is_token(value: &[u8]) -> bool {
+    !value.is_empty()
+        && value
+            .iter()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(byte))
+}
+
+/// Returns whether the path is the prefix, or a path below it.
+fn has_prefix(path: &str, prefix: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'),
+        None => false,
+    }
+}
+
+/// The parts of a request the override is decided from, so the decision can be
+/// shared by the middleware for each version of the `http` crate.
+pub(crate) trait Head {
+    fn method_name(&self) -> &str;
+    fn path(&self) -> &str;
+    fn query(&self) -> Option<&str>;
+    /// Returns the first value of the header with the given lowercase name.
+    fn header(&self, name: &str) -> Option<&[u8]>;
+    fn has_cookie(&self, name: &str) -> bool;
+}
+
+impl<Body> Head for Request<Body> {
+    fn method_name(&self) -> &str {
+        self.method().as_str()
+    }
+
+    fn path(&self) -> &str {
+        self.uri().path()
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.uri().query()
+    }
+
+    fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers().get(name).map(HeaderValue::as_bytes)
+    }
+
+    fn has_cookie(&self, name: &str) -> bool {
+        let values = self.headers().get_all(COOKIE);
+        has_cookie(values.iter().map(HeaderValue::as_bytes), name)
+    }
+}
+
+/// A request whose method can be overridden in place, so that overriding it
+/// can be shared by the middleware for each version of the `http` crate.
+pub(crate) trait OverrideRequest: Head {
+    /// Changes the method of the request, recording the original one in its
+    /// extensions.
+    fn set_method(&mut self, new_method: Method, source: OverrideSource);
+    /// Returns the override recorded by `set_method`, if there was one.
+    fn override_info(&self) -> Option<MethodOverrideInfo>;
+    /// Removes the named parameter from the query.
+    fn strip_param(&mut self, name: &str);
+}
+
+impl<Body> OverrideRequest for Request<Body> {
+    fn set_method(&mut self, new_method: Method, source: OverrideSource) {
+        let original = std::mem::replace(self.method_mut(), new_method);
+        self.extensions_mut()
+            .insert(MethodOverrideInfo { original, source });
+    }
+
+    fn override_info(&self) -> Option<MethodOverrideInfo> {
+        self.extensions().get::<MethodOverrideInfo>().cloned()
+    }
+
+    fn strip_param(&mut self, name: &str) {
+        strip_param(self.uri_mut(), name);
+    }
+}
+
+/// Returns the request's method, which is the same in both versions of the
+/// `http` crate.
+fn method_of(req: &impl Head) -> Method {
+    Method::from_bytes(req.method_name().as_bytes())
+        .expect("the request's method is a valid method")
+}
+
+/// Returns the request's media type, without any parameters.
+fn content_type(req: &impl Head) -> Option<&str> {
+    let content_type = std::str::from_utf8(req.header(CONTENT_TYPE.as_str())?).ok()?;
+    Some(content_type.split(';').next().unwrap_or("").trim())
+}
+
+pub(crate) fn has_cookie<'a>(values: impl Iterator<Item = &'a [u8]>, name: &str) -> bool {
+    values
+        .filter_map(|value| std::str::from_utf8(value).ok())
+        .flat_map(|value| value.split(';'))
+        .any(|cookie| cookie.split('=').next().unwrap_or("").trim() == name)
+}
+
+/// Returns the path and query of a URI with the parameters of the given name
+/// removed from the query, keeping the others in order.
+fn strip_query(path: &str, query: &str, name: &str) -> String {
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| {
+            let raw_name = pair.split('=').next().unwrap_or("");
+            decode_component(raw_name.as_bytes()).as_deref() != Some(name)
+        })
+        .collect();
+
+    let mut path_and_query = path.to_string();
+    if !kept.is_empty() {
+        path_and_query.push('?');
+        path_and_query.push_str(&kept.join("&"));
+    }
+    path_and_query
+}
+
+/// The `Uri` of each version of the `http` crate, so `strip_param` can be
+/// shared by the middleware for each.
+pub(crate) trait OverrideUri {
+    fn path(&self) -> &str;
+    fn query(&self) -> Option<&str>;
+    /// Replaces the path and query, keeping the rest of the URI. Left
+    /// unchanged if the new path and query can't be parsed.
+    fn set_path_and_query(&mut self, path_and_query: String);
+}
+
+impl OverrideUri for Uri {
+    fn path(&self) -> &str {
+        self.path()
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.query()
+    }
+
+    fn set_path_and_query(&mut self, path_and_query: String) {
+        let mut parts = self.clone().into_parts();
+        parts.path_and_query = path_and_query.parse().ok();
+        if let Ok(uri) = Uri::from_parts(parts) {
+            *self = uri;
+        }
+    }
+}
+
+/// Removes the parameters with the given name from the URI's query.
+pub(crate) fn strip_param(uri: &mut impl OverrideUri, name: &str) {
+    let path_and_query = match uri.query() {
+        Some(query) => strip_query(uri.path(), query, name),
+        None => return,
+    };
+    uri.set_path_and_query(path_and_query);
+}
+
+/// Returns the raw value of the first query parameter with the given name.
+///
+/// Names are decoded with `decode_component`, so a malformed name never
+/// matches.
+fn query_param<'a>(query: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    query.split(|&byte| byte == b'&').find_map(|pair| {
+        let mut parts = pair.splitn(2, |&byte| byte == b'=');
+        let raw_name = parts.next()?;
+        match decode_component(raw_name) {
+            Some(decoded) if decoded == name => Some(parts.next().unwrap_or(b"")),
+            _ => None,
+        }
+    })
+}
+
+fn decode_component(raw: &[u8]) -> Option<Cow<'_, str>> {
+    let mut bytes = raw.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'%' {
+            let digits = [bytes.next()?, bytes.next()?];
+            if !digits.iter().all(|digit| digit.is_ascii_hexdigit()) {
+                return None;
+            }
+        }
+    }
+
+    if raw.contains(&b'+') {
+        let raw: Vec<u8> = raw
+            .iter()
+            .map(|&byte| if byte == b'+' { b' ' } else { byte })
+            .collect();
+        let decoded = percent_decode(&raw).decode_utf8().ok()?;
+        Some(decoded.into_owned().into())
+    } else {
+        percent_decode(raw).decode_utf8().ok()
+    }
+}
+
+/// Scans the raw query for the parameter name, using the SIMD accelerated
+/// searchers in `memchr` where the CPU supports them. Any `%` could be part of
+/// a percent-encoded name, and any `+` an encoded space in a name with one, so
+/// those queries still have to be parsed.
+#[cfg(feature = "simd")]
+fn may_contain_param(query: &[u8], name: &str) -> bool {
+    memchr::memmem::find(query, name.as_bytes()).is_some()
+        || memchr::memchr(b'%', query).is_some()
+        || (name.contains(' ') && memchr::memchr(b'+', query).is_some())
+}
+
+#[cfg(not(feature = "simd"))]
+fn may_contain_param(_query: &[u8], _name: &str) -> bool {
+    true
+}
+
+/// Records an applied override as a tracing event, and in the
+/// `method_override` and `source` fields of the current span if it has them,
+/// as the spans of a `MethodOverrideMakeSpan` do.
+#[cfg(feature = "tracing")]
+fn trace_override(info: &MethodOverrideInfo, method: &Method, path: &str) {
+    tracing::debug!(
+        original = %info.original,
+        method = %method,
+        source = ?info.source,
+        path,
+        "overrode request method"
+    );
+    let span = tracing::Span::current();
+    span.record("method_override", method.as_str());
+    span.record(
+        "source",
+        match info.source {
+            OverrideSource::QueryParam => "query",
+            OverrideSource::Header => "header",
+            OverrideSource::FormBody => "form",
+        },
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_override(_info: &MethodOverrideInfo, _method: &Method, _path: &str) {}
+
+/// Records an override with a method from a source given to
+/// `Builder::deprecate_source` as a warning event.
+#[cfg(feature = "tracing")]
+fn trace_deprecated(info: &MethodOverrideInfo, method: &Method, path: &str) {
+    tracing::warn!(
+        original = %info.original,
+        method = %method,
+        source = ?info.source,
+        path,
+        "overrode request method from a deprecated source"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_deprecated(_info: &MethodOverrideInfo, _method: &Method, _path: &str) {}
+
+/// Records a request rejected for the method it was to be overridden to, in
+/// strict mode or by `BodyPolicy::Reject`, as a tracing event.
+#[cfg(feature = "tracing")]
+fn trace_rejection(original: &str, source: OverrideSource, path: &str, reason: RejectReason) {
+    tracing::debug!(
+        original,
+        source = ?source,
+        path,
+        reason = ?reason,
+        status = reason.status().as_u16(),
+        "rejected method override"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_rejection(_original: &str, _source: OverrideSource, _path: &str, _reason: RejectReason) {}
+
+/// Records a method that couldn't be used outside strict mode, so that the
+/// request was passed through with its original method, as a tracing event.
+#[cfg(feature = "tracing")]
+fn trace_ignored(original: &str, source: OverrideSource, path: &str, reason: RejectReason) {
+    tracing::debug!(
+        original,
+        source = ?source,
+        path,
+        reason = ?reason,
+        "ignored method override"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_ignored(_original: &str, _source: OverrideSource, _path: &str, _reason: RejectReason) {}
+
+/// Records the error a form body couldn't be read because of, as a tracing
+/// event.
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_body_error(path: &str, error: &dyn fmt::Display) {
+    tracing::debug!(path, error = %error, "failed to read form body");
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn trace_body_error(_path: &str, _error: &dyn fmt::Display) {}
+
+/// Returns the boundary of a `multipart/form-data` request, whose body can be
+/// read for the parameter with the `multipart` feature.
+#[cfg(feature = "multipart")]
+pub(crate) fn multipart_boundary(req: &impl Head) -> Option<String> {
+    let content_type = std::str::from_utf8(req.header(CONTENT_TYPE.as_str())?).ok()?;
+    multipart::boundary(content_type).map(String::from)
+}
+
+#[cfg(not(feature = "multipart"))]
+pub(crate) fn multipart_boundary(_req: &impl Head) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header::ALLOW;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        let body = format!("{:?}", req.method()).into();
+        Ok(Response::new(body))
+    }
+
+    /// Calls the service with the request, returning the status and body of
+    /// its response.
+    async fn call<S>(service: &mut S, req: Request<Body>) -> (StatusCode, String)
+    where
+        S: Service<Request<Body>, Response = Response<Body>>,
+        S::Error: fmt::Debug,
+    {
+        let res = service.call(req).await.unwrap();
+        let status = res.status();
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    async fn send(method: Method, url: &str) -> String {
+        let mut request = reqwest::Request::new(method, reqwest::Url::parse(url).unwrap());
+        request
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(FORM_CONTENT_TYPE));
+        reqwest::Client::new()
+            .execute(request)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn override_test() {
+        let addr = ([127, 0, 0, 1], 1337).into();
+
+        tokio::spawn(Server::bind(&addr).serve(make_service_fn(|_| async {
+            let service = MethodOverrideMiddleware::new(service_fn(handle));
+            Ok::<_, hyper::Error>(service)
+        })));
+
+        // No override requested
+        assert_eq!(send(Method::GET, "http://127.0.0.1:1337").await, "GET");
+        assert_eq!(send(Method::PUT, "http://127.0.0.1:1337").await, "PUT");
+        assert_eq!(send(Method::POST, "http://127.0.0.1:1337").await, "POST");
+        assert_eq!(send(Method::PATCH, "http://127.0.0.1:1337").await, "PATCH");
+
+        // Successful overrides
+        assert_eq!(
+            send(Method::POST, "http://127.0.0.1:1337?a=1&b=2&_method=PATCH").await,
+            "PATCH"
+        );
+        assert_eq!(
+            send(Method::POST, "http://127.0.0.1:1337?a=1&b=2&_method=PUT").await,
+            "PUT"
+        );
+        assert_eq!(
+            send(Method::POST, "http://127.0.0.1:1337?a=1&b=2&_method=DELETE").await,
+            "DELETE"
+        );
+
+        // Other methods cannot be specified for overrides
+        assert_eq!(
+            send(Method::POST, "http://127.0.0.1:1337?a=1&b=2&_method=GET").await,
+            "POST"
+        );
+        assert_eq!(
+            send(Method::POST, "http://127.0.0.1:1337?_method=OPTIONS").await,
+            "POST"
+        );
+
+        // Non-POST requests don't get overriden
+        assert_eq!(
+            send(Method::GET, "http://127.0.0.1:1337?_method=PATCH").await,
+            "GET"
+        );
+        assert_eq!(
+            send(Method::DELETE, "http://127.0.0.1:1337?_method=PUT").await,
+            "DELETE"
+        );
+        assert_eq!(
+            send(Method::PATCH, "http://127.0.0.1:1337?_method=DELETE").await,
+            "PATCH"
+        );
+    }
+
+    #[tokio::test]
+    async fn hint_supported_methods_test() {
+        async fn not_allowed(_: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+            Ok(res)
+        }
+
+        async fn hint(hint: bool, method: Method, uri: &str) -> Option<HeaderValue> {
+            let mut service = MethodOverrideMiddleware::builder()
+                .hint_supported_methods(hint)
+                .build(service_fn(not_allowed));
+            let req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+                .body(Body::empty());
+            let res = service.call(req.unwrap()).await.unwrap();
+            res.headers().get(SUPPORTED_HEADER).cloned()
+        }
+
+        // Plain POST requests get the hint
+        assert_eq!(
+            hint(true, Method::POST, "/").await.unwrap(),
+            "PUT, PATCH, DELETE"
+        );
+        assert!(hint(true, Method::POST, "/?_method=GET").await.is_some());
+
+        // Overridden and non-POST requests don't
+        assert_eq!(hint(true, Method::POST, "/?_method=PUT").await, None);
+        assert_eq!(hint(true, Method::GET, "/").await, None);
+
+        // Disabled by default
+        assert_eq!(hint(false, Method::POST, "/").await, None);
+    }
+
+    #[tokio::test]
+    async fn deprecate_source_test() {
+        async fn send(builder: Builder, req: http::request::Builder) -> Response<Body> {
+            let mut service = builder.header_override(true).build(service_fn(handle));
+            let req = req.header(CONTENT_TYPE, FORM_CONTENT_TYPE);
+            service
+                .call(req.body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+        }
+
+        let deprecated = || {
+            MethodOverrideMiddleware::builder()
+                .deprecate_source(OverrideSource::Header)
+                .deprecation_header("@1767225600")
+        };
+        let header = || Request::post("/").header("x-http-method-override", "DELETE");
+
+        // Deprecated sources are still used, and their responses marked
+        let res = send(deprecated(), header()).await;
+        assert_eq!(res.headers()[DEPRECATION_HEADER], "@1767225600");
+        let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, "DELETE");
+
+        // Other sources aren't marked
+        let res = send(deprecated(), Request::post("/?_method=DELETE")).await;
+        assert!(!res.headers().contains_key(DEPRECATION_HEADER));
+        let res = send(deprecated(), Request::post("/")).await;
+        assert!(!res.headers().contains_key(DEPRECATION_HEADER));
+
+        // The header is only added when set
+        let builder = MethodOverrideMiddleware::builder().deprecate_source(OverrideSource::Header);
+        let res = send(builder, header()).await;
+        assert!(!res.headers().contains_key(DEPRECATION_HEADER));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a valid header value")]
+    fn invalid_deprecation_header_test() {
+        MethodOverrideMiddleware::builder().deprecation_header("a\nb");
+    }
+
+    #[tokio::test]
+    async fn require_cookie_test() {
+        async fn method(cookies: &[&str], uri: &str) -> String {
+            let mut service = MethodOverrideMiddleware::builder()
+                .require_cookie("session")
+                .build(service_fn(handle));
+            let mut req = Request::post(uri).header(CONTENT_TYPE, FORM_CONTENT_TYPE);
+            for cookie in cookies {
+                req = req.header(COOKIE, *cookie);
+            }
+            call(&mut service, req.body(Body::empty()).unwrap()).await.1
+        }
+
+        // The cookie is present
+        assert_eq!(method(&["session=abc"], "/?_method=PUT").await, "PUT");
+        assert_eq!(method(&["a=1; session=abc"], "/?_method=PUT").await, "PUT");
+        assert_eq!(method(&["a=1", "session="], "/?_method=PUT").await, "PUT");
+
+        // The cookie is missing
+        assert_eq!(method(&[], "/?_method=PUT").await, "POST");
+        assert_eq!(method(&["sessions=abc"], "/?_method=PUT").await, "POST");
+        assert_eq!(method(&["a=session"], "/?_method=PUT").await, "POST");
+    }
+
+    #[tokio::test]
+    async fn scope_test() {
+        async fn method(builder: Builder, uri: &str) -> String {
+            let mut service = builder.build(service_fn(handle));
+            let req = Request::post(uri)
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+                .body(Body::empty())
+                .unwrap();
+            call(&mut service, req).await.1
+        }
+
+        // Paths below an excluded prefix are not overridden
+        let builder = || MethodOverrideMiddleware::builder().exclude_prefix("/api");
+        assert_eq!(method(builder(), "/api?_method=PUT").await, "POST");
+        assert_eq!(method(builder(), "/api/items?_method=PUT").await, "POST");
+        assert_eq!(method(builder(), "/apis?_method=PUT").await, "PUT");
+        assert_eq!(method(builder(), "/items?_method=PUT").await, "PUT");
+
+        // However the path is spelled
+        assert_eq!(method(builder(), "/%61pi/x?_method=PUT").await, "POST");
+        assert_eq!(method(builder(), "//api/x?_method=PUT").await, "POST");
+        assert_eq!(method(builder(), "/items/../api?_method=PUT").await, "POST");
+        assert_eq!(method(builder(), "/./api/?_method=PUT").await, "POST");
+        assert_eq!(method(builder(), "/%2561pi?_method=PUT").await, "PUT");
+
+        // Only paths below an included prefix are overridden
+        let builder = || {
+            MethodOverrideMiddleware::builder()
+                .include_prefix("/admin/")
+                .include_prefix("/account")
+                .exclude_prefix("/admin/api")
+        };
+        assert_eq!(method(builder(), "/admin/users?_method=PUT").await, "PUT");
+        assert_eq!(method(builder(), "/account?_method=PUT").await, "PUT");
+        assert_eq!(method(builder(), "/admin?_method=PUT").await, "POST");
+        assert_eq!(method(builder(), "/accounts?_method=PUT").await, "POST");
+        assert_eq!(method(builder(), "/admin/api?_method=PUT").await, "POST");
+
+        // Or those the filter allows
+        let builder = || {
+            MethodOverrideMiddleware::builder()
+                .filter(|req| req.method() == "POST" && req.query() != Some("_method=PUT&api"))
+        };
+        assert_eq!(method(builder(), "/?_method=PUT").await, "PUT");
+        assert_eq!(method(builder(), "/?_method=PUT&api").await, "POST");
+        let builder = MethodOverrideMiddleware::builder()
+            .filter(|req| req.header("Content-Type") != Some(FORM_CONTENT_TYPE.as_bytes()));
+        assert_eq!(method(builder, "/?_method=PUT").await, "POST");
+
+        // The filter is called once per request, even when the body is read and
+        // the response hinted
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let mut service = MethodOverrideMiddleware::builder()
+            .form_body(true)
+            .hint_supported_methods(true)
+            .filter(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                true
+            })
+            .build_with_body(service_fn(handle));
+        let req = Request::post("/")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .header(CONTENT_LENGTH, 3)
+            .body("a=1".into())
+            .unwrap();
+        service.call(req).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn body_policy_test() {
+        async fn echo(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let method = req.method().clone();
+            let length = req.headers().contains_key(CONTENT_LENGTH);
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let body = format!("{:?} {:?} {}", method, body, length);
+            Ok(Response::new(body.into()))
+        }
+
+        async fn send(policy: BodyPolicy, uri: &str, body: &'static str) -> (StatusCode, String) {
+            let mut service = MethodOverrideMiddleware::builder()
+                .body_policy(Method::DELETE, policy)
+                .build_with_body(service_fn(echo));
+            let req = Request::post(uri)
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+                .header(CONTENT_LENGTH, body.len())
+                .body(body.into())
+                .unwrap();
+            call(&mut service, req).await
+        }
+
+        let ok = StatusCode::OK;
+
+        // Bodies are kept
+        assert_eq!(
+            send(BodyPolicy::Keep, "/?_method=DELETE", "a=1").await,
+            (ok, r#"DELETE b"a=1" true"#.to_string())
+        );
+
+        // Bodies are stripped
+        assert_eq!(
+            send(BodyPolicy::Strip, "/?_method=DELETE", "a=1").await,
+            (ok, r#"DELETE b"" false"#.to_string())
+        );
+
+        // Requests with bodies are rejected
+        assert_eq!(
+            send(BodyPolicy::Reject, "/?_method=DELETE", "a=1").await,
+            (StatusCode::BAD_REQUEST, "".to_string())
+        );
+        assert_eq!(
+            send(BodyPolicy::Reject, "/?_method=DELETE", "").await,
+            (ok, r#"DELETE b"" true"#.to_string())
+        );
+
+        // Policies only apply to their method
+        assert_eq!(
+            send(BodyPolicy::Reject, "/?_method=PUT", "a=1").await,
+            (ok, r#"PUT b"a=1" true"#.to_string())
+        );
+        assert_eq!(
+            send(BodyPolicy::Reject, "/", "a=1").await,
+            (ok, r#"POST b"a=1" true"#.to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn form_body_test() {
+        async fn echo(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let method = req.method().clone();
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            Ok(Response::new(format!("{:?} {:?}", method, body).into()))
+        }
+
+        async fn send(
+            limit: Option<usize>,
+            uri: &str,
+            content_type: &str,
+            body: &'static str,
+        ) -> String {
+            let mut builder = MethodOverrideMiddleware::builder();
+            if let Some(limit) = limit {
+                builder = builder.form_body(true).form_body_limit(limit);
+            }
+            let mut service = builder.build_with_body(service_fn(echo));
+            let req = Request::post(uri)
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_LENGTH, body.len())
+                .body(body.into())
+                .unwrap();
+            call(&mut service, req).await.1
+        }
+
+        let enabled = Some(DEFAULT_FORM_BODY_LIMIT);
+        let form = "application/x-www-form-urlencoded";
+
+        // The parameter is read from the body, which is passed on unchanged
+        assert_eq!(
+            send(enabled, "/", form, "a=1&_method=DELETE").await,
+            r#"DELETE b"a=1&_method=DELETE""#
+        );
+        assert_eq!(
+            send(
+                enabled,
+                "/",
+                "application/x-www-form-urlencoded; charset=UTF-8",
+                "_method=PUT"
+            )
+            .await,
+            r#"PUT b"_method=PUT""#
+        );
+        assert_eq!(
+            send(enabled, "/", form, "_method=GET").await,
+            r#"POST b"_method=GET""#
+        );
+
+        // Bodies arriving in several chunks are read in full
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            sender.send_data("a=1&_met".into()).await.unwrap();
+            sender.send_data("hod=PATCH".into()).await.unwrap();
+        });
+        let req = Request::post("/")
+            .header(CONTENT_TYPE, form)
+            .header(CONTENT_LENGTH, 17)
+            .body(body)
+            .unwrap();
+        let mut service = MethodOverrideMiddleware::builder()
+            .form_body(true)
+            .build_with_body(service_fn(echo));
+        assert_eq!(
+            call(&mut service, req).await.1,
+            r#"PATCH b"a=1&_method=PATCH""#
+        );
+
+        // The query parameter takes precedence
+        assert_eq!(
+            send(enabled, "/?_method=PATCH", form, "_method=DELETE").await,
+            r#"PATCH b"_method=DELETE""#
+        );
+
+        // Other bodies are not read
+        assert_eq!(
+            send(enabled, "/", "application/json", "_method=DELETE").await,
+            r#"POST b"_method=DELETE""#
+        );
+        assert_eq!(
+            send(Some(4), "/", form, "_method=DELETE").await,
+            r#"POST b"_method=DELETE""#
+        );
+
+        // Disabled by default
+        assert_eq!(
+            send(None, "/", form, "_method=DELETE").await,
+            r#"POST b"_method=DELETE""#
+        );
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn may_contain_param_test() {
+        assert!(may_contain_param(b"a=1&_method=PUT", "_method"));
+        assert!(may_contain_param(b"%5Fmethod=PUT", "_method"));
+        assert!(may_contain_param(b"my+method=PUT", "my method"));
+
+        assert!(!may_contain_param(b"a=1&b=2", "_method"));
+        assert!(!may_contain_param(b"a+b=1", "_method"));
+    }
+
+    #[test]
+    fn apply_override_sync_test() {
+        let post = |uri| {
+            Request::post(uri)
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+                .body(())
+                .unwrap()
+        };
+
+        // The method is overridden and the parameter removed
+        let mut req = post("/item?a=1&_method=DELETE");
+        assert!(apply_override_sync(&mut req));
+        assert_eq!(req.method(), Method::DELETE);
+        assert_eq!(req.uri(), "/item?a=1");
+        assert_eq!(
+            req.extensions().get::<MethodOverrideInfo>(),
+            Some(&MethodOverrideInfo {
+                original: Method::POST,
+                source: OverrideSource::QueryParam,
+            })
+        );
+
+        // Requests that can't be overridden are left unchanged
+        for mut req in [
+            post("/?_method=GET"),
+            post("/?a=1"),
+            Request::put("/?_method=DELETE").body(()).unwrap(),
+            Request::post("/?_method=DELETE").body(()).unwrap(),
+        ] {
+            let uri = req.uri().clone();
+            assert!(!apply_override_sync(&mut req));
+            assert_eq!(req.uri(), &uri);
+            assert_eq!(req.extensions().get::<MethodOverrideInfo>(), None);
+        }
+    }
+
+    #[test]
+    fn sync_method_override_test() {
+        let overrides = Arc::new(AtomicUsize::new(0));
+        let hook_overrides = overrides.clone();
+        let method_override = MethodOverrideMiddleware::builder()
+            .param_name("_override")
+            .allow_method(Method::OPTIONS)
+            .strict(true)
+            .body_policy(Method::PATCH, BodyPolicy::Reject)
+            .on_override(move |_: &MethodOverrideInfo| {
+                hook_overrides.fetch_add(1, Ordering::SeqCst);
+            })
+            .build_sync();
+        let apply = |uri, body| {
+            let mut req = Request::post(uri)
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+                .header(CONTENT_LENGTH, body)
+                .body(())
+                .unwrap();
+            let result = method_override.apply(&mut req);
+            (result, req.method().clone(), req.uri().clone())
+        };
+
+        assert_eq!(
+            apply("/?_override=OPTIONS", 0),
+            (Ok(true), Method::OPTIONS, "/".parse().unwrap())
+        );
+        assert_eq!(
+            apply("/?_method=PUT", 0),
+            (Ok(false), Method::POST, "/?_method=PUT".parse().unwrap())
+        );
+        assert_eq!(
+            apply("/?_override=TRACE", 0),
+            (
+                Err(RejectReason::MethodNotAllowed),
+                Method::POST,
+                "/?_override=TRACE".parse().unwrap()
+            )
+        );
+        assert_eq!(
+            apply("/?_override=PATCH", 3).0,
+            Err(RejectReason::BodyNotAllowed)
+        );
+        assert_eq!(overrides.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "which SyncMethodOverride can't do")]
+    fn sync_form_body_test() {
+        MethodOverrideMiddleware::builder()
+            .form_body(true)
+            .build_sync();
+    }
+
+    #[test]
+    #[should_panic(expected = "Builder::build_with_body")]
+    fn build_without_body_test() {
+        MethodOverrideMiddleware::builder()
+            .body_policy(Method::DELETE, BodyPolicy::Strip)
+            .build(());
+    }
+
+    #[cfg(feature = "multipart")]
+    #[tokio::test]
+    async fn multipart_test() {
+        async fn echo(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let method = req.method().clone();
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            Ok(Response::new(format!("{:?} {}", method, body).into()))
+        }
 
-use hyper::{service::Service, Method, Request};
-use std::borrow::Borrow;
-use std::task::{Context, Poll};
-use url::form_urlencoded;
+        async fn send(strict: bool, content_type: &str, body: &str) -> (StatusCode, String) {
+            let mut service = MethodOverrideMiddleware::builder()
+                .form_body(true)
+                .strict(strict)
+                .build_with_body(service_fn(echo));
+            let req = Request::post("/")
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_LENGTH, body.len())
+                .body(body.to_string().into())
+                .unwrap();
+            call(&mut service, req).await
+        }
 
-#[derive(Debug, Clone)]
-pub struct MethodOverrideMiddleware<T> {
-    inner_service: T,
-}
+        let multipart = "multipart/form-data; boundary=XyZ";
+        let upload = "--XyZ\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+            \r\n\
+            _method=PUT\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"_method\"\r\n\
+            \r\n\
+            DELETE\r\n\
+            --XyZ--\r\n";
+        let ok = |method: &str, body: &str| (StatusCode::OK, format!("{} {}", method, body));
 
-impl<T> MethodOverrideMiddleware<T> {
-    pub fn new(inner_service: T) -> Self {
-        Self { inner_service }
-    }
-}
+        // The field is read from the body, which is passed on unchanged
+        assert_eq!(send(false, multipart, upload).await, ok("DELETE", upload));
 
-impl<InnerService, Body> Service<Request<Body>> for MethodOverrideMiddleware<InnerService>
-where
-    InnerService: Service<Request<Body>>,
-{
-    type Response = InnerService::Response;
-    type Error = InnerService::Error;
-    type Future = InnerService::Future;
+        // The same methods are allowed as for the query parameter
+        let get = "--XyZ\r\n\
+            Content-Disposition: form-data; name=\"_method\"\r\n\
+            \r\n\
+            GET\r\n\
+            --XyZ--\r\n";
+        assert_eq!(send(false, multipart, get).await, ok("POST", get));
+        assert_eq!(
+            send(true, multipart, get).await,
+            (StatusCode::METHOD_NOT_ALLOWED, "".to_string())
+        );
 
-    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.inner_service.poll_ready(cx)
+        // Uploads longer than the form body limit are read up to their own
+        let file = "x".repeat(DEFAULT_FORM_BODY_LIMIT * 2);
+        let large = format!(
+            "--XyZ\r\n\
+            Content-Disposition: form-data; name=\"_method\"\r\n\
+            \r\n\
+            PUT\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+            \r\n\
+            {}\r\n\
+            --XyZ--\r\n",
+            file
+        );
+        assert_eq!(send(false, multipart, &large).await, ok("PUT", &large));
+        let form = "application/x-www-form-urlencoded";
+        let large_form = format!("_method=PUT&a={}", file);
+        assert_eq!(
+            send(false, form, &large_form).await,
+            ok("POST", &large_form)
+        );
+
+        // Bodies with a different boundary, or none, are not overridden
+        let other = "multipart/form-data; boundary=other";
+        assert_eq!(send(false, other, upload).await, ok("POST", upload));
+        let missing = "multipart/form-data";
+        assert_eq!(send(false, missing, upload).await, ok("POST", upload));
     }
 
-    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
-        if let Some(new_method) = override_method(&req) {
-            *req.method_mut() = new_method;
+    #[tokio::test]
+    async fn builder_test() {
+        async fn method(builder: Builder, method: Method, uri: &str) -> String {
+            let mut service = builder.build(service_fn(handle));
+            let req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE);
+            call(&mut service, req.body(Body::empty()).unwrap()).await.1
         }
-        self.inner_service.call(req)
-    }
-}
 
-fn override_method<Body>(req: &Request<Body>) -> Option<Method> {
-    if req.method() != &Method::POST {
-        return None;
+        let post = Method::POST;
+
+        // The parameter name can be changed
+        let builder = || MethodOverrideMiddleware::builder().param_name("X-Method");
+        assert_eq!(
+            method(builder(), post.clone(), "/?X-Method=PUT").await,
+            "PUT"
+        );
+        assert_eq!(
+            method(builder(), post.clone(), "/?_method=PUT").await,
+            "POST"
+        );
+
+        // Including to one with a space, given as a `+`
+        let builder = || MethodOverrideMiddleware::builder().param_name("my method");
+        assert_eq!(
+            method(builder(), post.clone(), "/?my+method=PUT").await,
+            "PUT"
+        );
+
+        // More methods can be allowed
+        let builder = || MethodOverrideMiddleware::builder().allow_method(Method::OPTIONS);
+        assert_eq!(
+            method(builder(), post.clone(), "/?_method=OPTIONS").await,
+            "OPTIONS"
+        );
+        assert_eq!(
+            method(builder(), post.clone(), "/?_method=PUT").await,
+            "PUT"
+        );
+
+        // Or the allowed methods replaced
+        let builder = || MethodOverrideMiddleware::builder().allowed_methods(vec![Method::DELETE]);
+        assert_eq!(
+            method(builder(), post.clone(), "/?_method=DELETE").await,
+            "DELETE"
+        );
+        assert_eq!(
+            method(builder(), post.clone(), "/?_method=PUT").await,
+            "POST"
+        );
+
+        // Requests of other methods can be overridden
+        let builder = || MethodOverrideMiddleware::builder().source_methods(vec![Method::GET]);
+        assert_eq!(method(builder(), Method::GET, "/?_method=PUT").await, "PUT");
+        assert_eq!(
+            method(builder(), post.clone(), "/?_method=PUT").await,
+            "POST"
+        );
+
+        // The hint lists the allowed methods
+        async fn not_allowed(_: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::METHOD_NOT_ALLOWED;
+            Ok(res)
+        }
+        let mut service = MethodOverrideMiddleware::builder()
+            .allow_method(Method::OPTIONS)
+            .hint_supported_methods(true)
+            .build(service_fn(not_allowed));
+        let res = service
+            .call(Request::post("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            res.headers()[SUPPORTED_HEADER],
+            "PUT, PATCH, DELETE, OPTIONS"
+        );
     }
 
-    form_urlencoded::parse(req.uri().query().unwrap_or("").as_bytes())
-        .find(|(param_name, _)| param_name == "_method")
-        .and_then(|(_, method)| match method.borrow() {
-            "DELETE" => Some(Method::DELETE),
-            "PATCH" => Some(Method::PATCH),
-            "PUT" => Some(Method::PUT),
-            _ => None,
-        })
-}
+    #[tokio::test]
+    async fn preset_test() {
+        async fn send(
+            preset: Preset,
+            uri: &str,
+            content_type: &str,
+            header: Option<&str>,
+            body: &'static str,
+        ) -> (StatusCode, String) {
+            let mut service = Builder::from_preset(preset).build_with_body(service_fn(handle));
+            let mut req = Request::post(uri)
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_LENGTH, body.len());
+            if let Some(method) = header {
+                req = req.header("x-http-method-override", method);
+            }
+            call(&mut service, req.body(body.into()).unwrap()).await
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use hyper::service::{make_service_fn, service_fn};
-    use hyper::{Body, Request, Response, Server};
-    use std::convert::Infallible;
+        let form = FORM_CONTENT_TYPE;
+        let json = "application/json";
+        let ok = |method: &str| (StatusCode::OK, method.to_string());
 
-    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
-        let body = format!("{:?}", req.method()).into();
-        Ok(Response::new(body))
+        // Rails reads form bodies and headers, and allows its extra methods
+        let rails = Preset::RailsCompatible;
+        assert_eq!(
+            send(rails, "/", form, None, "_method=LINK").await,
+            ok("LINK")
+        );
+        assert_eq!(send(rails, "/", json, Some("PUT"), "").await, ok("PUT"));
+        assert_eq!(
+            send(rails, "/?_method=PUT", json, None, "").await,
+            ok("POST")
+        );
+
+        // Express reads headers, and the query whatever the content type
+        let express = Preset::ExpressCompatible;
+        assert_eq!(send(express, "/", json, Some("PUT"), "").await, ok("PUT"));
+        assert_eq!(
+            send(express, "/?_method=PUT", json, None, "").await,
+            ok("PUT")
+        );
+        assert_eq!(
+            send(express, "/", form, None, "_method=PUT").await,
+            ok("POST")
+        );
+
+        // Laravel reads all three, and allows OPTIONS
+        let laravel = Preset::LaravelCompatible;
+        assert_eq!(
+            send(laravel, "/", form, None, "_method=OPTIONS").await,
+            ok("OPTIONS")
+        );
+        assert_eq!(
+            send(laravel, "/?_method=PUT", json, None, "").await,
+            ok("PUT")
+        );
+        assert_eq!(
+            send(laravel, "/", form, None, "_method=LINK").await,
+            ok("POST")
+        );
+
+        // The strict preset only reads the query of forms, and rejects methods
+        // that can't be used
+        let strict = Preset::StrictSecure;
+        assert_eq!(
+            send(strict, "/?_method=PUT", form, None, "").await,
+            ok("PUT")
+        );
+        assert_eq!(
+            send(strict, "/?_method=PUT", json, None, "").await,
+            ok("POST")
+        );
+        assert_eq!(send(strict, "/", form, Some("PUT"), "").await, ok("POST"));
+        assert_eq!(
+            send(strict, "/?_method=TRACE", form, None, "").await.0,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+
+        // Presets can be tweaked further
+        let mut service = Builder::from_preset(Preset::Permissive)
+            .header_override(false)
+            .build_with_body(service_fn(handle));
+        let req = Request::post("/?_method=UNLINK")
+            .header(CONTENT_TYPE, json)
+            .header("x-http-method-override", "PUT")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(call(&mut service, req).await, ok("UNLINK"));
     }
 
-    async fn send(method: Method, url: &str) -> String {
-        reqwest::Client::new()
-            .execute(reqwest::Request::new(
-                method,
-                reqwest::Url::parse(url).unwrap(),
-            ))
-            .await
-            .unwrap()
-            .text()
-            .await
-            .unwrap()
+    #[tokio::test]
+    async fn method_resolver_test() {
+        async fn send(builder: Builder, req: http::request::Builder) -> (StatusCode, String) {
+            let mut service = builder.header_override(true).build(service_fn(handle));
+            let req = req.header(CONTENT_TYPE, FORM_CONTENT_TYPE);
+            call(&mut service, req.body(Body::empty()).unwrap()).await
+        }
+
+        let default = MethodOverrideMiddleware::builder;
+        let ok = |method: &str| (StatusCode::OK, method.to_string());
+
+        // Values are case-insensitive by default
+        assert_eq!(
+            send(default(), Request::post("/?_method=delete")).await,
+            ok("DELETE")
+        );
+        assert_eq!(
+            send(default(), Request::post("/?_method=Put")).await,
+            ok("PUT")
+        );
+        let header = Request::post("/").header("x-http-method-override", "patch");
+        assert_eq!(send(default(), header).await, ok("PATCH"));
+        assert_eq!(
+            send(default(), Request::post("/?_method=get")).await,
+            ok("POST")
+        );
+
+        // A resolver can map the application's own names
+        let custom = || {
+            default().strict(true).method_resolver(|value| match value {
+                "destroy" => Some(Method::DELETE),
+                "get" => Some(Method::GET),
+                "PUT" => Some(Method::PUT),
+                _ => None,
+            })
+        };
+        assert_eq!(
+            send(custom(), Request::post("/?_method=destroy")).await,
+            ok("DELETE")
+        );
+        assert_eq!(
+            send(custom(), Request::post("/?_method=PUT")).await,
+            ok("PUT")
+        );
+
+        // Values it doesn't know are not methods, and its methods must be allowed
+        assert_eq!(
+            send(custom(), Request::post("/?_method=put")).await,
+            (StatusCode::BAD_REQUEST, "".to_string())
+        );
+        assert_eq!(
+            send(custom(), Request::post("/?_method=get")).await,
+            (StatusCode::METHOD_NOT_ALLOWED, "".to_string())
+        );
     }
 
     #[tokio::test]
-    async fn override_test() {
-        let addr = ([127, 0, 0, 1], 1337).into();
+    async fn header_override_test() {
+        async fn method(
+            builder: Builder,
+            method: Method,
+            uri: &str,
+            headers: &[(&str, &str)],
+        ) -> String {
+            let mut service = builder.build(service_fn(handle));
+            let mut req = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE);
+            for (name, value) in headers {
+                req = req.header(*name, *value);
+            }
+            call(&mut service, req.body(Body::empty()).unwrap()).await.1
+        }
 
-        tokio::spawn(Server::bind(&addr).serve(make_service_fn(|_| async {
-            let service = MethodOverrideMiddleware::new(service_fn(handle));
-            Ok::<_, hyper::Error>(service)
-        })));
+        let enabled = || MethodOverrideMiddleware::builder().header_override(true);
+        let post = Method::POST;
 
-        // No override requested
-        assert_eq!(send(Method::GET, "http://127.0.0.1:1337").await, "GET");
-        assert_eq!(send(Method::PUT, "http://127.0.0.1:1337").await, "PUT");
-        assert_eq!(send(Method::POST, "http://127.0.0.1:1337").await, "POST");
-        assert_eq!(send(Method::PATCH, "http://127.0.0.1:1337").await, "PATCH");
+        // Either header can be used
+        let header = [("x-http-method-override", "PUT")];
+        assert_eq!(method(enabled(), post.clone(), "/", &header).await, "PUT");
+        let header = [("x-http-method", "DELETE")];
+        assert_eq!(
+            method(enabled(), post.clone(), "/", &header).await,
+            "DELETE"
+        );
+        let headers = [
+            ("x-http-method", "DELETE"),
+            ("x-http-method-override", "PUT"),
+        ];
+        assert_eq!(method(enabled(), post.clone(), "/", &headers).await, "PUT");
 
-        // Successful overrides
+        // The same methods are allowed as for the query parameter
+        let header = [("x-http-method-override", "GET")];
+        assert_eq!(method(enabled(), post.clone(), "/", &header).await, "POST");
+        let header = [("x-http-method-override", "PUT")];
+        assert_eq!(method(enabled(), Method::GET, "/", &header).await, "GET");
+
+        // The query parameter takes precedence unless configured otherwise
+        let header = [("x-http-method-override", "PUT")];
         assert_eq!(
-            send(Method::POST, "http://127.0.0.1:1337?a=1&b=2&_method=PATCH").await,
+            method(enabled(), post.clone(), "/?_method=PATCH", &header).await,
             "PATCH"
         );
+        let header_first = || enabled().precedence(Precedence::Header);
         assert_eq!(
-            send(Method::POST, "http://127.0.0.1:1337?a=1&b=2&_method=PUT").await,
+            method(header_first(), post.clone(), "/?_method=PATCH", &header).await,
             "PUT"
         );
+        let header = [("x-http-method-override", "GET")];
         assert_eq!(
-            send(Method::POST, "http://127.0.0.1:1337?a=1&b=2&_method=DELETE").await,
-            "DELETE"
+            method(header_first(), post.clone(), "/?_method=PATCH", &header).await,
+            "PATCH"
         );
 
-        // Other methods cannot be specified for overrides
+        // Headers can be required to come from JavaScript clients
+        let required = || enabled().require_requested_with(true);
+        let header = [("x-http-method-override", "PUT")];
+        assert_eq!(method(required(), post.clone(), "/", &header).await, "POST");
+        let headers = [
+            ("x-http-method-override", "PUT"),
+            ("x-requested-with", "xmlhttprequest"),
+        ];
+        assert_eq!(method(required(), post.clone(), "/", &headers).await, "PUT");
+        let headers = [
+            ("x-http-method-override", "PUT"),
+            ("x-requested-with", "Fetch"),
+        ];
         assert_eq!(
-            send(Method::POST, "http://127.0.0.1:1337?a=1&b=2&_method=GET").await,
+            method(required(), post.clone(), "/", &headers).await,
             "POST"
         );
+        let fetch = || enabled().requested_with("Fetch");
+        assert_eq!(method(fetch(), post.clone(), "/", &headers).await, "PUT");
         assert_eq!(
-            send(Method::POST, "http://127.0.0.1:1337?_method=OPTIONS").await,
+            method(required(), post.clone(), "/?_method=PATCH", &header).await,
+            "PATCH"
+        );
+
+        // Disabled by default
+        let header = [("x-http-method-override", "PUT")];
+        assert_eq!(
+            method(
+                MethodOverrideMiddleware::builder(),
+                post.clone(),
+                "/",
+                &header
+            )
+            .await,
             "POST"
         );
+    }
 
-        // Non-POST requests don't get overriden
+    #[tokio::test]
+    async fn content_type_test() {
+        async fn method(builder: Builder, content_type: Option<&str>, headers: bool) -> String {
+            let mut service = builder.header_override(headers).build(service_fn(handle));
+            let mut req = Request::post("/?_method=PUT");
+            if let Some(content_type) = content_type {
+                req = req.header(CONTENT_TYPE, content_type);
+            }
+            if headers {
+                req = req.header("x-http-method-override", "DELETE");
+            }
+            call(&mut service, req.body(Body::empty()).unwrap()).await.1
+        }
+
+        let default = MethodOverrideMiddleware::builder;
+
+        // Form submissions are overridden, ignoring parameters and case
         assert_eq!(
-            send(Method::GET, "http://127.0.0.1:1337?_method=PATCH").await,
-            "GET"
+            method(default(), Some(FORM_CONTENT_TYPE), false).await,
+            "PUT"
         );
+        let multipart = "multipart/form-data; boundary=abc";
+        assert_eq!(method(default(), Some(multipart), false).await, "PUT");
+        let charset = "Application/X-WWW-Form-Urlencoded; charset=UTF-8";
+        assert_eq!(method(default(), Some(charset), false).await, "PUT");
+
+        // Other requests are not
+        let json = Some("application/json");
+        assert_eq!(method(default(), json, false).await, "POST");
+        assert_eq!(method(default(), None, false).await, "POST");
+
+        // Override headers are still used
+        assert_eq!(method(default(), json, true).await, "DELETE");
+
+        // The content types can be replaced
+        let json_only = || default().content_types(vec!["application/json"]);
+        assert_eq!(method(json_only(), json, false).await, "PUT");
         assert_eq!(
-            send(Method::DELETE, "http://127.0.0.1:1337?_method=PUT").await,
-            "DELETE"
+            method(json_only(), Some(FORM_CONTENT_TYPE), false).await,
+            "POST"
+        );
+
+        // Or the check disabled
+        let unchecked = || default().check_content_type(false);
+        assert_eq!(method(unchecked(), json, false).await, "PUT");
+        assert_eq!(method(unchecked(), None, false).await, "PUT");
+    }
+
+    #[tokio::test]
+    async fn override_info_test() {
+        async fn info(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let info = req.extensions().get::<MethodOverrideInfo>();
+            Ok(Response::new(format!("{:?}", info).into()))
+        }
+
+        async fn send(req: Request<Body>) -> String {
+            let mut service = MethodOverrideMiddleware::builder()
+                .header_override(true)
+                .form_body(true)
+                .build_with_body(service_fn(info));
+            call(&mut service, req).await.1
+        }
+
+        let req = Request::post("/?_method=PUT")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .body(Body::empty());
+        assert_eq!(
+            send(req.unwrap()).await,
+            "Some(MethodOverrideInfo { original: POST, source: QueryParam })"
         );
+
+        let req = Request::post("/")
+            .header("x-http-method-override", "DELETE")
+            .body(Body::empty());
         assert_eq!(
-            send(Method::PATCH, "http://127.0.0.1:1337?_method=DELETE").await,
-            "PATCH"
+            send(req.unwrap()).await,
+            "Some(MethodOverrideInfo { original: POST, source: Header })"
+        );
+
+        let req = Request::post("/")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header(CONTENT_LENGTH, 13)
+            .body("_method=PATCH".into());
+        assert_eq!(
+            send(req.unwrap()).await,
+            "Some(MethodOverrideInfo { original: POST, source: FormBody })"
+        );
+
+        // Requests that weren't overridden have no info
+        let req = Request::delete("/?_method=PUT")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .body(Body::empty());
+        assert_eq!(send(req.unwrap()).await, "None");
+        let req = Request::post("/?_method=GET")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .body(Body::empty());
+        assert_eq!(send(req.unwrap()).await, "None");
+    }
+
+    #[tokio::test]
+    async fn on_override_test() {
+        use std::sync::Mutex;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let hook_seen = seen.clone();
+        let mut service = MethodOverrideMiddleware::builder()
+            .header_override(true)
+            .body_policy(Method::PATCH, BodyPolicy::Reject)
+            .on_override(move |info: &MethodOverrideInfo| {
+                hook_seen.lock().unwrap().push(info.clone());
+            })
+            .build(service_fn(handle));
+
+        for req in [
+            Request::post("/?_method=PUT").header(CONTENT_TYPE, FORM_CONTENT_TYPE),
+            Request::post("/").header("x-http-method-override", "DELETE"),
+            Request::post("/?_method=GET").header(CONTENT_TYPE, FORM_CONTENT_TYPE),
+            Request::get("/?_method=PUT").header(CONTENT_TYPE, FORM_CONTENT_TYPE),
+        ] {
+            service
+                .call(req.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+        }
+        let rejected = Request::post("/?_method=PATCH")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .header(CONTENT_LENGTH, 3)
+            .body("a=1".into())
+            .unwrap();
+        service.call(rejected).await.unwrap();
+
+        // Only the requests passed on overridden are reported
+        let info = |source| MethodOverrideInfo {
+            original: Method::POST,
+            source,
+        };
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                info(OverrideSource::QueryParam),
+                info(OverrideSource::Header)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn on_reject_test() {
+        use std::sync::Mutex;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let hook_seen = seen.clone();
+        let mut service = MethodOverrideMiddleware::builder()
+            .strict(true)
+            .header_override(true)
+            .body_policy(Method::PATCH, BodyPolicy::Reject)
+            .on_reject(move |rejection: &MethodOverrideRejection| {
+                hook_seen.lock().unwrap().push(rejection.clone());
+            })
+            .build(service_fn(handle));
+
+        for req in [
+            Request::post("/?_method=PU%T").header(CONTENT_TYPE, FORM_CONTENT_TYPE),
+            Request::post("/?_method=P%20T").header(CONTENT_TYPE, FORM_CONTENT_TYPE),
+            Request::post("/").header("x-http-method-override", "TRACE"),
+            Request::post("/?_method=PUT").header(CONTENT_TYPE, FORM_CONTENT_TYPE),
+        ] {
+            service
+                .call(req.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+        }
+        let rejected = Request::post("/?_method=PATCH")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .header(CONTENT_LENGTH, 3)
+            .body("a=1".into())
+            .unwrap();
+        service.call(rejected).await.unwrap();
+
+        // Malformed encoding is told apart from values that aren't methods
+        let rejection = |source, reason| MethodOverrideRejection {
+            original: Method::POST,
+            source,
+            reason,
+            passed_through: false,
+        };
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                rejection(OverrideSource::QueryParam, RejectReason::MalformedEncoding),
+                rejection(OverrideSource::QueryParam, RejectReason::NotAMethod),
+                rejection(OverrideSource::Header, RejectReason::MethodNotAllowed),
+                rejection(OverrideSource::QueryParam, RejectReason::BodyNotAllowed),
+            ]
+        );
+
+        // So are form bodies that turn out too long, or can't be read
+        seen.lock().unwrap().clear();
+        let hook_seen = seen.clone();
+        let mut service = MethodOverrideMiddleware::builder()
+            .form_body(true)
+            .form_body_limit(4)
+            .on_reject(move |rejection: &MethodOverrideRejection| {
+                hook_seen.lock().unwrap().push(rejection.clone());
+            })
+            .build_with_body(service_fn(handle));
+
+        let too_large = Request::post("/")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .header(CONTENT_LENGTH, 3)
+            .body("_method=PUT".into())
+            .unwrap();
+        assert_eq!(
+            call(&mut service, too_large).await.0,
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        let (sender, body) = Body::channel();
+        sender.abort();
+        let unreadable = Request::post("/")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .header(CONTENT_LENGTH, 3)
+            .body(body)
+            .unwrap();
+        assert_eq!(
+            call(&mut service, unreadable).await.0,
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                rejection(OverrideSource::FormBody, RejectReason::BodyTooLarge),
+                rejection(OverrideSource::FormBody, RejectReason::BodyUnreadable),
+            ]
+        );
+
+        // Outside strict mode, values that can't be used are reported as
+        // passed through
+        seen.lock().unwrap().clear();
+        let hook_seen = seen.clone();
+        let mut service = MethodOverrideMiddleware::builder()
+            .header_override(true)
+            .form_body(true)
+            .on_reject(move |rejection: &MethodOverrideRejection| {
+                hook_seen.lock().unwrap().push(rejection.clone());
+            })
+            .build_with_body(service_fn(handle));
+
+        for req in [
+            Request::post("/?_method=PU%T").header(CONTENT_TYPE, FORM_CONTENT_TYPE),
+            Request::post("/").header("x-http-method-override", "TRACE"),
+        ] {
+            let req = req.body(Body::empty()).unwrap();
+            assert_eq!(
+                call(&mut service, req).await,
+                (StatusCode::OK, "POST".into())
+            );
+        }
+        let ignored = Request::post("/")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .header(CONTENT_LENGTH, 11)
+            .body("_method=GET".into())
+            .unwrap();
+        assert_eq!(
+            call(&mut service, ignored).await,
+            (StatusCode::OK, "POST".into())
+        );
+
+        let passed_through = |source, reason| MethodOverrideRejection {
+            passed_through: true,
+            ..rejection(source, reason)
+        };
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                passed_through(OverrideSource::QueryParam, RejectReason::MalformedEncoding),
+                passed_through(OverrideSource::Header, RejectReason::MethodNotAllowed),
+                passed_through(OverrideSource::FormBody, RejectReason::MethodNotAllowed),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn strict_test() {
+        async fn send(strict: bool, req: http::request::Builder) -> (StatusCode, String) {
+            let mut service = MethodOverrideMiddleware::builder()
+                .strict(strict)
+                .header_override(true)
+                .form_body(true)
+                .build_with_body(service_fn(handle));
+            call(&mut service, req.body(Body::empty()).unwrap()).await
+        }
+
+        let post = |uri| Request::post(uri).header(CONTENT_TYPE, FORM_CONTENT_TYPE);
+        let ok = |method: &str| (StatusCode::OK, method.to_string());
+        let bad_request = (StatusCode::BAD_REQUEST, "".to_string());
+        let not_allowed = (StatusCode::METHOD_NOT_ALLOWED, "".to_string());
+
+        // Allowed methods are overridden as usual
+        assert_eq!(send(true, post("/?_method=PUT")).await, ok("PUT"));
+        assert_eq!(send(true, post("/?a=1")).await, ok("POST"));
+
+        // Methods that aren't allowed are rejected
+        assert_eq!(send(true, post("/?_method=TRACE")).await, not_allowed);
+        assert_eq!(send(true, post("/?_method=trace")).await, not_allowed);
+        let header = post("/").header("x-http-method-override", "GET");
+        assert_eq!(send(true, header).await, not_allowed);
+
+        // As are values that aren't methods
+        assert_eq!(send(true, post("/?_method=")).await, bad_request);
+        assert_eq!(send(true, post("/?_method=PU%T")).await, bad_request);
+        assert_eq!(send(true, post("/?_method=P%20T")).await, bad_request);
+
+        // Including in form bodies
+        let mut service = MethodOverrideMiddleware::builder()
+            .strict(true)
+            .form_body(true)
+            .build_with_body(service_fn(handle));
+        let req = Request::post("/")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .header(CONTENT_LENGTH, 13)
+            .body("_method=TRACE".into())
+            .unwrap();
+        let res = service.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[ALLOW], "POST, PUT, PATCH, DELETE");
+        assert!(!res.headers().contains_key(SUPPORTED_HEADER));
+
+        // Rejections list the methods the request could have used, and are
+        // hinted when enabled
+        let mut service = MethodOverrideMiddleware::builder()
+            .strict(true)
+            .hint_supported_methods(true)
+            .build(service_fn(handle));
+        let req = post("/?_method=TRACE").body(Body::empty()).unwrap();
+        let res = service.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[ALLOW], "POST, PUT, PATCH, DELETE");
+        assert_eq!(res.headers()[SUPPORTED_HEADER], "PUT, PATCH, DELETE");
+
+        // And carry their reason for a layer around the middleware to render
+        let reason = res.extensions().get::<RejectReason>();
+        assert_eq!(reason, Some(&RejectReason::MethodNotAllowed));
+        let req = post("/?_method=PUT").body(Body::empty()).unwrap();
+        let res = service.call(req).await.unwrap();
+        assert_eq!(res.extensions().get::<RejectReason>(), None);
+
+        // Requests that can't be overridden aren't checked
+        let get = Request::get("/?_method=TRACE").header(CONTENT_TYPE, FORM_CONTENT_TYPE);
+        assert_eq!(send(true, get).await, ok("GET"));
+
+        // Disabled by default
+        assert_eq!(send(false, post("/?_method=TRACE")).await, ok("POST"));
+        assert_eq!(send(false, post("/?_method=PU%T")).await, ok("POST"));
+    }
+
+    #[tokio::test]
+    async fn strip_param_test() {
+        async fn echo(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            Ok(Response::new(
+                format!("{} {}", req.method(), req.uri()).into(),
+            ))
+        }
+
+        async fn send(strip: bool, uri: &str) -> String {
+            let mut service = MethodOverrideMiddleware::builder()
+                .strip_param(strip)
+                .build(service_fn(echo));
+            let req = Request::post(uri)
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+                .body(Body::empty())
+                .unwrap();
+            call(&mut service, req).await.1
+        }
+
+        // The parameter is removed, keeping the others in order
+        assert_eq!(
+            send(true, "/items?b=2&_method=DELETE&a=1").await,
+            "DELETE /items?b=2&a=1"
         );
+        assert_eq!(send(true, "/items?_method=PUT").await, "PUT /items");
+        assert_eq!(send(true, "/?%5Fmethod=PUT&_method=GET&c").await, "PUT /?c");
+        assert_eq!(
+            send(true, "http://example.com/?_method=PUT").await,
+            "PUT http://example.com/"
+        );
+
+        // Requests that weren't overridden are left alone
+        assert_eq!(send(true, "/?_method=GET").await, "POST /?_method=GET");
+
+        // Unless disabled
+        assert_eq!(send(false, "/?_method=PUT").await, "PUT /?_method=PUT");
+    }
+
+    #[cfg(feature = "tower")]
+    #[tokio::test]
+    async fn boxed_test() {
+        use tower::ServiceExt;
+
+        async fn shout(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let body = format!("{:?}!", req.method()).into();
+            Ok(Response::new(body))
+        }
+
+        let services: Vec<BoxedMethodOverride<Body, Response<Body>, Infallible>> = vec![
+            MethodOverrideMiddleware::boxed(service_fn(handle)),
+            MethodOverrideMiddleware::boxed(service_fn(shout)),
+        ];
+
+        let mut bodies = Vec::new();
+        for service in services {
+            let req = Request::post("/?_method=DELETE")
+                .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+                .body(Body::empty())
+                .unwrap();
+            let res = service.oneshot(req).await.unwrap();
+            bodies.push(hyper::body::to_bytes(res.into_body()).await.unwrap());
+        }
+        assert_eq!(bodies, ["DELETE", "DELETE!"]);
     }
 }
@@ -0,0 +1,183 @@
+//! Just enough of a `multipart/form-data` parser, as described in RFC 7578, to
+//! find the value of a field in a buffered body. The body itself is passed on
+//! to the inner service unchanged, so file parts are skipped over rather than
+//! decoded.
+//!
+
+/// Returns the boundary of a `multipart/form-data` content type, or `None`
+/// for other content types and those without a usable boundary.
+pub(crate) fn boundary(content_type: &str) -> Option<&str> {
+    let mut params = content_type.split(';');
+    if !params
+        .next()?
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+
+    let boundary = params.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("boundary") {
+            Some(unquote(value.trim()))
+        } else {
+            None
+        }
+    })?;
+
+    // RFC 2046 limits boundaries to 70 characters.
+    if boundary.is_empty() || boundary.len() > 70 {
+        None
+    } else {
+        Some(boundary)
+    }
+}
+
+/// Returns the value of the first part of the body that is the named field,
+/// rather than a file. A body that is malformed before that part is reached
+/// has no fields.
+pub(crate) fn field<'a>(body: &'a [u8], boundary: &str, name: &str) -> Option<&'a [u8]> {
+    let delimiter = format!("\r\n--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+
+    // The first delimiter may start the body, without a line break before it.
+    let mut rest = match body.strip_prefix(&delimiter[2..]) {
+        Some(rest) => rest,
+        None => &body[find(body, delimiter)? + delimiter.len()..],
+    };
+
+    loop {
+        // The final delimiter is followed by `--`, the others by optional
+        // whitespace and a line break.
+        if rest.starts_with(b"--") {
+            return None;
+        }
+        rest = &rest[find(rest, b"\r\n")? + 2..];
+
+        let mut is_field = false;
+        loop {
+            let end = find(rest, b"\r\n")?;
+            let line = &rest[..end];
+            rest = &rest[end + 2..];
+            if line.is_empty() {
+                break;
+            }
+            if let Some(disposition) = header_value(line, "content-disposition") {
+                is_field = is_named_field(disposition, name);
+            }
+        }
+
+        let end = find(rest, delimiter)?;
+        if is_field {
+            return Some(&rest[..end]);
+        }
+        rest = &rest[end + delimiter.len()..];
+    }
+}
+
+fn header_value<'a>(line: &'a [u8], name: &str) -> Option<&'a str> {
+    let (line_name, value) = std::str::from_utf8(line).ok()?.split_once(':')?;
+    if line_name.trim().eq_ignore_ascii_case(name) {
+        Some(value.trim())
+    } else {
+        None
+    }
+}
+
+/// Returns whether a `Content-Disposition` header is that of the named field.
+/// Parts with a filename are files, even when they have the same name.
+fn is_named_field(disposition: &str, name: &str) -> bool {
+    let mut params = disposition.split(';');
+    if !matches!(params.next(), Some(kind) if kind.trim().eq_ignore_ascii_case("form-data")) {
+        return false;
+    }
+
+    let mut has_name = false;
+    for param in params {
+        let (key, value) = match param.split_once('=') {
+            Some(param) => param,
+            None => continue,
+        };
+        match key.trim().to_ascii_lowercase().as_str() {
+            "name" => has_name = unquote(value.trim()) == name,
+            "filename" | "filename*" => return false,
+            _ => (),
+        }
+    }
+    has_name
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+#[cfg(feature = "simd")]
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    memchr::memmem::find(haystack, needle)
+}
+
+#[cfg(not(feature = "simd"))]
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundary_test() {
+        assert_eq!(boundary("multipart/form-data; boundary=abc"), Some("abc"));
+        assert_eq!(
+            boundary("Multipart/Form-Data; charset=utf-8; BOUNDARY=\"a:b=c\""),
+            Some("a:b=c")
+        );
+
+        assert_eq!(boundary("multipart/form-data"), None);
+        assert_eq!(boundary("multipart/form-data; boundary="), None);
+        assert_eq!(boundary("multipart/mixed; boundary=abc"), None);
+        assert_eq!(boundary("application/x-www-form-urlencoded"), None);
+    }
+
+    #[test]
+    fn field_test() {
+        let body = b"--abc\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\
+            \r\n\
+            Hello\r\n\
+            --abc\r\n\
+            Content-Disposition: form-data; name=\"_method\"; filename=\"a.txt\"\r\n\
+            Content-Type: text/plain\r\n\
+            \r\n\
+            PUT\r\n\
+            --abc\r\n\
+            content-disposition: form-data; name=_method\r\n\
+            \r\n\
+            DELETE\r\n\
+            --abc--\r\n";
+        assert_eq!(field(body, "abc", "_method"), Some(&b"DELETE"[..]));
+        assert_eq!(field(body, "abc", "title"), Some(&b"Hello"[..]));
+        assert_eq!(field(body, "abc", "other"), None);
+        assert_eq!(field(body, "xyz", "_method"), None);
+
+        // A preamble before the first delimiter is skipped
+        let body = b"preamble\r\n--abc\r\n\
+            Content-Disposition: form-data; name=\"_method\"\r\n\
+            \r\n\
+            PATCH\r\n\
+            --abc--";
+        assert_eq!(field(body, "abc", "_method"), Some(&b"PATCH"[..]));
+
+        // Parts that aren't closed by a delimiter are ignored
+        let body = b"--abc\r\n\
+            Content-Disposition: form-data; name=\"_method\"\r\n\
+            \r\n\
+            PATCH";
+        assert_eq!(field(body, "abc", "_method"), None);
+    }
+}
@@ -0,0 +1,235 @@
+//! A utoipa [`Modify`](utoipa::Modify) implementation that documents the
+//! method override in generated OpenAPI documents.
+//!
+//!    #[derive(OpenApi)]
+//!    #[openapi(paths(delete_item), modifiers(&MethodOverrideModifier))]
+//!    struct ApiDoc;
+//!
+//! The derive only takes modifiers that are unit structs, so the one for a
+//! configured middleware, from `Builder::openapi_modifier`, is applied to the
+//! document afterwards:
+//!
+//!    let mut openapi = ApiDoc::openapi();
+//!    builder.openapi_modifier().modify(&mut openapi);
+//!
+use crate::{Builder, Options};
+use http::Method;
+use serde_json::json;
+use std::sync::Arc;
+use utoipa::openapi::path::{Operation, ParameterBuilder, ParameterIn};
+use utoipa::openapi::{ObjectBuilder, OpenApi, PathItem, Required, Type};
+
+/// Annotates each PUT, PATCH, and DELETE operation with an
+/// `x-method-override` extension describing how to reach it with a POST
+/// request, and adds the optional `_method` query parameter to the POST
+/// operation on the same path.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MethodOverrideModifier;
+
+impl utoipa::Modify for MethodOverrideModifier {
+    fn modify(&self, openapi: &mut OpenApi) {
+        annotate(openapi, &Options::default());
+    }
+}
+
+/// Does what `MethodOverrideModifier` does for a configured middleware, using
+/// its parameter name, the methods it overrides to, and those of the requests
+/// it overrides. Created with `Builder::openapi_modifier`.
+#[derive(Debug, Clone, Default)]
+pub struct ConfiguredMethodOverrideModifier {
+    options: Arc<Options>,
+}
+
+impl utoipa::Modify for ConfiguredMethodOverrideModifier {
+    fn modify(&self, openapi: &mut OpenApi) {
+        annotate(openapi, &self.options);
+    }
+}
+
+impl Builder {
+    /// Returns a utoipa modifier that documents the method override with this
+    /// configuration.
+    pub fn openapi_modifier(self) -> ConfiguredMethodOverrideModifier {
+        ConfiguredMethodOverrideModifier {
+            options: Arc::new(self.into_options()),
+        }
+    }
+}
+
+fn annotate(openapi: &mut OpenApi, options: &Options) {
+    for item in openapi.paths.paths.values_mut() {
+        annotate_path(item, options);
+    }
+}
+
+fn annotate_path(item: &mut PathItem, options: &Options) {
+    let source = match options.source_methods.first() {
+        Some(source) => source.as_str(),
+        None => return,
+    };
+    let name = options.param_name.as_ref();
+
+    let mut targets = Vec::new();
+    for method in options.allowed_methods.iter() {
+        if let Some(operation) = operation(item, method) {
+            annotate_target(operation, source, name, method.as_str());
+            targets.push(method.as_str());
+        }
+    }
+    if targets.is_empty() {
+        return;
+    }
+
+    for method in options.source_methods.iter() {
+        if let Some(operation) = operation(item, method) {
+            let parameter = ParameterBuilder::new()
+                .name(name)
+                .parameter_in(ParameterIn::Query)
+                .required(Required::False)
+                .description(Some(format!(
+                    "Handle this {} request as the given method instead.",
+                    method
+                )))
+                .schema(Some(
+                    ObjectBuilder::new()
+                        .schema_type(Type::String)
+                        .enum_values(Some(targets.clone())),
+                ))
+                .build();
+            operation
+                .parameters
+                .get_or_insert_with(Vec::new)
+                .push(parameter.into());
+        }
+    }
+}
+
+/// Returns the path's operation for a method, which is one of its extra
+/// operations for methods the OpenAPI specification doesn't name.
+fn operation<'a>(item: &'a mut PathItem, method: &Method) -> Option<&'a mut Operation> {
+    match *method {
+        Method::GET => item.get.as_mut(),
+        Method::PUT => item.put.as_mut(),
+        Method::POST => item.post.as_mut(),
+        Method::DELETE => item.delete.as_mut(),
+        Method::OPTIONS => item.options.as_mut(),
+        Method::HEAD => item.head.as_mut(),
+        Method::PATCH => item.patch.as_mut(),
+        Method::TRACE => item.trace.as_mut(),
+        _ => item.additional_operations.get_mut(method.as_str()),
+    }
+}
+
+fn annotate_target(operation: &mut Operation, source: &str, name: &str, method: &str) {
+    operation
+        .extensions
+        .get_or_insert_with(Default::default)
+        .insert(
+            "x-method-override".into(),
+            json!({ "method": source, "parameter": name, "value": method }),
+        );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utoipa::openapi::path::{HttpMethod, Parameter};
+    use utoipa::openapi::{OpenApiBuilder, PathsBuilder, RefOr};
+    use utoipa::Modify;
+
+    fn document(paths: PathsBuilder) -> OpenApi {
+        let mut openapi = OpenApiBuilder::new().paths(paths).build();
+        MethodOverrideModifier.modify(&mut openapi);
+        openapi
+    }
+
+    fn query_parameters(operation: &Operation) -> Vec<&Parameter> {
+        operation
+            .parameters
+            .iter()
+            .flatten()
+            .filter_map(|parameter| match parameter {
+                RefOr::T(parameter) => Some(parameter),
+                RefOr::Ref(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn modify_test() {
+        let openapi = document(
+            PathsBuilder::new()
+                .path(
+                    "/item/{id}",
+                    PathItem::from_http_methods(
+                        [HttpMethod::Get, HttpMethod::Post, HttpMethod::Delete],
+                        Operation::new(),
+                    ),
+                )
+                .path(
+                    "/items",
+                    PathItem::from_http_methods(
+                        [HttpMethod::Get, HttpMethod::Post],
+                        Operation::new(),
+                    ),
+                ),
+        );
+
+        // Override targets are annotated
+        let item = &openapi.paths.paths["/item/{id}"];
+        let extensions = item.delete.as_ref().unwrap().extensions.as_ref().unwrap();
+        assert_eq!(
+            extensions["x-method-override"],
+            json!({ "method": "POST", "parameter": "_method", "value": "DELETE" })
+        );
+        assert!(item.get.as_ref().unwrap().extensions.is_none());
+
+        // POST operations on the same path accept the parameter
+        let parameters = query_parameters(item.post.as_ref().unwrap());
+        assert_eq!(parameters.len(), 1);
+        assert_eq!(parameters[0].name, "_method");
+
+        // Paths without override targets are left alone
+        let items = &openapi.paths.paths["/items"];
+        assert!(query_parameters(items.post.as_ref().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn builder_test() {
+        let paths = PathsBuilder::new().path(
+            "/item/{id}",
+            PathItem::from_http_methods(
+                [
+                    HttpMethod::Get,
+                    HttpMethod::Post,
+                    HttpMethod::Delete,
+                    HttpMethod::Options,
+                ],
+                Operation::new(),
+            ),
+        );
+        let mut openapi = OpenApiBuilder::new().paths(paths).build();
+        Builder::new()
+            .param_name("_override")
+            .allowed_methods(["OPTIONS"])
+            .source_methods(["GET", "POST"])
+            .openapi_modifier()
+            .modify(&mut openapi);
+
+        // Only the configured methods are targets
+        let item = &openapi.paths.paths["/item/{id}"];
+        assert!(item.delete.as_ref().unwrap().extensions.is_none());
+        let extensions = item.options.as_ref().unwrap().extensions.as_ref().unwrap();
+        assert_eq!(
+            extensions["x-method-override"],
+            json!({ "method": "GET", "parameter": "_override", "value": "OPTIONS" })
+        );
+
+        // Each source method accepts the configured parameter
+        for operation in [&item.get, &item.post] {
+            let parameters = query_parameters(operation.as_ref().unwrap());
+            assert_eq!(parameters.len(), 1);
+            assert_eq!(parameters[0].name, "_override");
+        }
+    }
+}
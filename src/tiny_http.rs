@@ -0,0 +1,223 @@
+//! Method overriding for synchronous [tiny_http](https://docs.rs/tiny_http)
+//! servers, which don't allow the method of a request to be changed.
+//!
+//!    for request in server.incoming_requests() {
+//!        match (request_method(&request), request.url()) {
+//!            ...
+//!        }
+//!    }
+//!
+//! It uses the default configuration. A `MethodOverride` configured with the
+//! same builder as the other adapters is returned by `Builder::build_tiny_http`:
+//!
+//!    let method_override = MethodOverrideMiddleware::builder()
+//!        .header_override(true)
+//!        .strict(true)
+//!        .build_tiny_http();
+//!
+//!    for request in server.incoming_requests() {
+//!        let method = match method_override.request_method(&request) {
+//!            Ok(method) => method,
+//!            Err(reason) => {
+//!                request.respond(method_override.rejection_response(reason))?;
+//!                continue;
+//!            }
+//!        };
+//!        match (method, request.url()) {
+//!            ...
+//!        }
+//!    }
+//!
+
+use crate::{has_cookie, Builder, Head, Options, RejectReason};
+use ::tiny_http::{Header, Method, Request, Response};
+use std::io::Empty;
+use std::sync::Arc;
+
+/// Returns the method the request should be handled as, which is either the
+/// method from the request's `_method` query parameter or its actual method.
+pub fn request_method(request: &Request) -> Method {
+    MethodOverride::default()
+        .request_method(request)
+        .expect("the default configuration doesn't reject requests")
+}
+
+/// Finds the method requests should be handled as with a configuration from
+/// `Builder::build_tiny_http`.
+#[derive(Debug, Clone, Default)]
+pub struct MethodOverride {
+    options: Arc<Options>,
+}
+
+impl MethodOverride {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the method the request should be handled as, which is either
+    /// the method it is overridden to or its actual method, or in strict mode
+    /// and for methods with `BodyPolicy::Reject` why the request should be
+    /// rejected. `rejection_response` is the response the middleware sends
+    /// for those.
+    pub fn request_method(&self, request: &Request) -> Result<Method, RejectReason> {
+        let new_method = self
+            .options
+            .override_method(request)?
+            .and_then(|(new_method, _)| new_method.as_str().parse().ok());
+        Ok(new_method.unwrap_or_else(|| request.method().clone()))
+    }
+
+    /// Returns the empty 400 or 405 response the middleware sends to requests
+    /// it rejects, with an `Allow` header on 405s.
+    pub fn rejection_response(&self, reason: RejectReason) -> Response<Empty> {
+        let status = reason.status();
+        let mut res = Response::empty(status.as_u16());
+        for (name, value) in self.options.reject_headers(status) {
+            let header = Header::from_bytes(name.as_str(), value.as_bytes())
+                .expect("the middleware's headers are valid tiny_http headers");
+            res = res.with_header(header);
+        }
+        res
+    }
+}
+
+impl Builder {
+    /// Returns a tiny_http `MethodOverride` with this configuration. The
+    /// parameter stays in the request's URL, as tiny_http requests can't be
+    /// changed.
+    ///
+    /// Panics if `form_body` is enabled or a body policy is
+    /// `BodyPolicy::Strip`.
+    pub fn build_tiny_http(self) -> MethodOverride {
+        self.assert_no_body("which tiny_http::MethodOverride can't do");
+        MethodOverride {
+            options: Arc::new(self.into_options()),
+        }
+    }
+}
+
+impl Head for Request {
+    fn method_name(&self) -> &str {
+        self.method().as_str()
+    }
+
+    fn path(&self) -> &str {
+        self.url().split('?').next().unwrap_or("")
+    }
+
+    fn query(&self) -> Option<&str> {
+        self.url().split_once('?').map(|(_, query)| query)
+    }
+
+    fn header(&self, name: &str) -> Option<&[u8]> {
+        self.headers()
+            .iter()
+            .find(|header| header.field.as_str().as_str().eq_ignore_ascii_case(name))
+            .map(|header| header.value.as_bytes())
+    }
+
+    fn has_cookie(&self, name: &str) -> bool {
+        let values = self
+            .headers()
+            .iter()
+            .filter(|header| header.field.equiv("cookie"))
+            .map(|header| header.value.as_bytes());
+        has_cookie(values, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BodyPolicy;
+    use crate::FORM_CONTENT_TYPE;
+    use ::tiny_http::{StatusCode, TestRequest};
+
+    fn request(method: Method, path: &str) -> Request {
+        TestRequest::new()
+            .with_method(method)
+            .with_path(path)
+            .with_header(Header::from_bytes("Content-Type", FORM_CONTENT_TYPE).unwrap())
+            .into()
+    }
+
+    fn method(method: Method, path: &str) -> Method {
+        request_method(&request(method, path))
+    }
+
+    #[test]
+    fn request_method_test() {
+        assert_eq!(method(Method::Post, "/"), Method::Post);
+        assert_eq!(method(Method::Post, "/?_method=DELETE"), Method::Delete);
+        assert_eq!(method(Method::Post, "/?_method=PATCH"), Method::Patch);
+        assert_eq!(method(Method::Post, "/?_method=GET"), Method::Post);
+        assert_eq!(method(Method::Get, "/?_method=PUT"), Method::Get);
+    }
+
+    #[test]
+    fn builder_test() {
+        let method_override = Builder::new()
+            .param_name("_override")
+            .allow_method("OPTIONS")
+            .source_methods(["POST", "GET"])
+            .build_tiny_http();
+        let method = |method, path| {
+            method_override
+                .request_method(&request(method, path))
+                .unwrap()
+        };
+
+        assert_eq!(method(Method::Post, "/?_override=OPTIONS"), Method::Options);
+        assert_eq!(method(Method::Get, "/?_override=DELETE"), Method::Delete);
+        assert_eq!(method(Method::Post, "/?_method=DELETE"), Method::Post);
+        assert_eq!(method(Method::Put, "/?_override=DELETE"), Method::Put);
+    }
+
+    #[test]
+    #[should_panic(expected = "which tiny_http::MethodOverride can't do")]
+    fn form_body_test() {
+        Builder::new().form_body(true).build_tiny_http();
+    }
+
+    #[test]
+    fn reject_test() {
+        let method_override = Builder::new()
+            .strict(true)
+            .body_policy("DELETE", BodyPolicy::Reject)
+            .build_tiny_http();
+        let method = |path, body: &'static str| {
+            let request: Request = TestRequest::new()
+                .with_method(Method::Post)
+                .with_path(path)
+                .with_header(Header::from_bytes("Content-Type", FORM_CONTENT_TYPE).unwrap())
+                .with_header(Header::from_bytes("Content-Length", body.len().to_string()).unwrap())
+                .with_body(body)
+                .into();
+            method_override.request_method(&request)
+        };
+
+        assert_eq!(
+            method("/?_method=TRACE", ""),
+            Err(RejectReason::MethodNotAllowed)
+        );
+        assert_eq!(method("/?_method=P%20T", ""), Err(RejectReason::NotAMethod));
+        assert_eq!(
+            method("/?_method=DELETE", "a=1"),
+            Err(RejectReason::BodyNotAllowed)
+        );
+        assert_eq!(method("/?_method=DELETE", ""), Ok(Method::Delete));
+        assert_eq!(method("/?_method=PUT", "a=1"), Ok(Method::Put));
+
+        // The rejection responses are the middleware's
+        let res = method_override.rejection_response(RejectReason::MethodNotAllowed);
+        assert_eq!(res.status_code(), StatusCode(405));
+        let allow = res
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("allow"))
+            .map(|header| header.value.as_str());
+        assert_eq!(allow, Some("POST, PUT, PATCH, DELETE"));
+        let res = method_override.rejection_response(RejectReason::BodyNotAllowed);
+        assert_eq!(res.status_code(), StatusCode(400));
+    }
+}
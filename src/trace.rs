@@ -0,0 +1,182 @@
+//! A tower-http [`MakeSpan`](tower_http::trace::MakeSpan) for a `TraceLayer`
+//! around the middleware, whose spans get the method a request was overridden
+//! to and where it was given:
+//!
+//!    let app = ServiceBuilder::new()
+//!        .layer(TraceLayer::new_for_http().make_span_with(MethodOverrideMakeSpan::new()))
+//!        .layer(MethodOverrideLayer::new())
+//!        .service(app);
+//!
+
+use http::Request;
+use tracing::field::Empty;
+use tracing::{Level, Span};
+
+/// Makes the same `request` spans as tower-http's `DefaultMakeSpan`, with the
+/// `method`, `uri`, and `version` of the request, and empty `method_override`
+/// and `source` fields that the middleware records an override in, such as
+/// `method_override=DELETE source=query`.
+#[derive(Debug, Clone)]
+pub struct MethodOverrideMakeSpan {
+    level: Level,
+}
+
+impl MethodOverrideMakeSpan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the level of the spans, `DEBUG` by default.
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+}
+
+impl Default for MethodOverrideMakeSpan {
+    fn default() -> Self {
+        Self {
+            level: Level::DEBUG,
+        }
+    }
+}
+
+impl<B> tower_http::trace::MakeSpan<B> for MethodOverrideMakeSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        // The level given to `span!` has to be a constant.
+        macro_rules! make_span {
+            ($level:expr) => {
+                tracing::span!(
+                    $level,
+                    "request",
+                    method = %request.method(),
+                    uri = %request.uri(),
+                    version = ?request.version(),
+                    method_override = Empty,
+                    source = Empty,
+                )
+            };
+        }
+
+        match self.level {
+            Level::ERROR => make_span!(Level::ERROR),
+            Level::WARN => make_span!(Level::WARN),
+            Level::INFO => make_span!(Level::INFO),
+            Level::DEBUG => make_span!(Level::DEBUG),
+            Level::TRACE => make_span!(Level::TRACE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MethodOverrideMiddleware, FORM_CONTENT_TYPE};
+    use http::header::CONTENT_TYPE;
+    use hyper::service::service_fn;
+    use hyper::{Body, Response};
+    use std::convert::Infallible;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tower_http::trace::MakeSpan;
+    use tower_service::Service;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::subscriber::Interest;
+    use tracing::{Event, Metadata, Subscriber};
+    use tracing_core::span::Current;
+
+    /// Keeps the fields recorded in spans after they were created, and which
+    /// span was entered.
+    #[derive(Default)]
+    struct Recorder {
+        recorded: Arc<Mutex<Vec<String>>>,
+        current: Mutex<Option<(Id, &'static Metadata<'static>)>>,
+        metadata: Mutex<Option<&'static Metadata<'static>>>,
+    }
+
+    impl Visit for &Recorder {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            let field = format!("{}={}", field.name(), value);
+            self.recorded.lock().unwrap().push(field);
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.record_str(field, &format!("{:?}", value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn register_callsite(&self, _: &'static Metadata<'static>) -> Interest {
+            Interest::always()
+        }
+
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            *self.metadata.lock().unwrap() = Some(span.metadata());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            let mut visitor = self;
+            values.record(&mut visitor);
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, span: &Id) {
+            let metadata = self.metadata.lock().unwrap().unwrap();
+            *self.current.lock().unwrap() = Some((span.clone(), metadata));
+        }
+
+        fn exit(&self, _: &Id) {
+            *self.current.lock().unwrap() = None;
+        }
+
+        fn current_span(&self) -> Current {
+            match *self.current.lock().unwrap() {
+                Some((ref id, metadata)) => Current::new(id.clone(), metadata),
+                None => Current::none(),
+            }
+        }
+    }
+
+    async fn handle(_req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        Ok(Response::new(Body::empty()))
+    }
+
+    #[tokio::test]
+    async fn make_span_test() {
+        let recorder = Recorder::default();
+        let recorded = recorder.recorded.clone();
+        let _guard = tracing::subscriber::set_default(recorder);
+
+        let mut service = MethodOverrideMiddleware::new(service_fn(handle));
+        let mut send = |req: Request<Body>| {
+            let span = MethodOverrideMakeSpan::new().make_span(&req);
+            span.in_scope(|| service.call(req))
+        };
+
+        // Overrides are recorded in the request's span
+        let req = Request::post("/?_method=DELETE")
+            .header(CONTENT_TYPE, FORM_CONTENT_TYPE)
+            .body(Body::empty())
+            .unwrap();
+        send(req).await.unwrap();
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec!["method_override=DELETE", "source=query"]
+        );
+
+        // And requests that aren't overridden record nothing
+        recorded.lock().unwrap().clear();
+        let req = Request::post("/").body(Body::empty()).unwrap();
+        send(req).await.unwrap();
+        assert!(recorded.lock().unwrap().is_empty());
+    }
+}
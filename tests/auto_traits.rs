@@ -0,0 +1,157 @@
+//! The public types must stay usable from multithreaded runtimes.
+
+use hyper::service::{service_fn, Service};
+use hyper::{Body, Method, Request, Response};
+use hyper_method_override_middleware::{
+    BodyMethodOverride, BodyResponseFuture, Builder, MethodOverrideMiddleware, ResponseFuture,
+    SyncMethodOverride,
+};
+use static_assertions::assert_impl_all;
+use std::convert::Infallible;
+use std::task::{Context, Poll};
+
+// The middleware must not add anything that is not Send, Sync, or Unpin to
+// the service it wraps.
+assert_impl_all!(MethodOverrideMiddleware<()>: Send, Sync, Unpin, Clone);
+assert_impl_all!(ResponseFuture<<ReadyService as Service<Request<Body>>>::Future>: Send, Unpin);
+assert_impl_all!(BodyMethodOverride<()>: Send, Sync, Unpin, Clone);
+assert_impl_all!(BodyResponseFuture<ReadyService, Body>: Send, Unpin);
+assert_impl_all!(Builder: Send, Sync, Unpin, Clone);
+assert_impl_all!(SyncMethodOverride: Send, Sync, Unpin, Clone);
+
+#[derive(Clone)]
+struct ReadyService;
+
+impl Service<Request<Body>> for ReadyService {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = std::future::Ready<Result<Response<Body>, Infallible>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Request<Body>) -> Self::Future {
+        std::future::ready(Ok(Response::new(Body::empty())))
+    }
+}
+
+#[cfg(feature = "tower")]
+assert_impl_all!(
+    hyper_method_override_middleware::BoxedMethodOverride<Body, Response<Body>, Infallible>:
+        Send,
+        Clone
+);
+
+#[cfg(feature = "tower")]
+assert_impl_all!(hyper_method_override_middleware::MethodOverrideLayer: Send, Sync, Unpin, Clone);
+
+#[cfg(feature = "hyper-1")]
+assert_impl_all!(
+    hyper_method_override_middleware::hyper1::MethodOverride<()>: Send,
+    Sync,
+    Unpin,
+    Clone
+);
+
+#[cfg(feature = "hyper-1")]
+assert_impl_all!(
+    hyper_method_override_middleware::hyper1::ResponseFuture<std::future::Ready<()>>: Send,
+    Sync,
+    Unpin
+);
+
+#[cfg(feature = "hyper-1")]
+assert_impl_all!(
+    hyper_method_override_middleware::hyper1::BodyMethodOverride<()>: Send,
+    Sync,
+    Unpin,
+    Clone
+);
+
+#[cfg(feature = "hyper-1")]
+assert_impl_all!(
+    hyper_method_override_middleware::hyper1::BodyResponseFuture<
+        (),
+        Body,
+        std::future::Ready<()>,
+    >: Send,
+    Unpin
+);
+
+#[cfg(feature = "axum")]
+assert_impl_all!(hyper_method_override_middleware::axum::OriginalMethod: Send, Sync, Unpin, Clone);
+
+#[cfg(feature = "axum")]
+assert_impl_all!(
+    hyper_method_override_middleware::axum::OverriddenMethod: Send,
+    Sync,
+    Unpin,
+    Clone
+);
+
+#[cfg(feature = "utoipa")]
+assert_impl_all!(hyper_method_override_middleware::MethodOverrideModifier: Send, Sync, Unpin);
+
+#[cfg(feature = "utoipa")]
+assert_impl_all!(
+    hyper_method_override_middleware::ConfiguredMethodOverrideModifier: Send,
+    Sync,
+    Unpin,
+    Clone
+);
+
+#[cfg(feature = "gotham")]
+assert_impl_all!(
+    hyper_method_override_middleware::gotham::MethodOverride: Send,
+    Sync,
+    Unpin,
+    std::panic::RefUnwindSafe
+);
+
+#[cfg(feature = "gotham")]
+assert_impl_all!(
+    hyper_method_override_middleware::gotham::MethodOverrideHandler<gotham::router::Router>:
+        Send,
+        Sync,
+        Unpin,
+        std::panic::RefUnwindSafe
+);
+
+#[cfg(feature = "tiny_http")]
+assert_impl_all!(
+    hyper_method_override_middleware::tiny_http::MethodOverride: Send,
+    Sync,
+    Unpin,
+    Clone
+);
+
+#[tokio::test(threaded_scheduler)]
+async fn multithreaded_test() {
+    async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+        Ok(Response::new(req.method().as_str().to_owned().into()))
+    }
+
+    let service = MethodOverrideMiddleware::new(service_fn(handle));
+
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let mut service = service.clone();
+            tokio::spawn(async move {
+                std::future::poll_fn(|cx| service.poll_ready(cx))
+                    .await
+                    .unwrap();
+                let req = Request::post("/?_method=PUT")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::empty())
+                    .unwrap();
+                let res = service.call(req).await.unwrap();
+                hyper::body::to_bytes(res.into_body()).await.unwrap()
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        assert_eq!(task.await.unwrap(), Method::PUT.as_str());
+    }
+}
@@ -0,0 +1,42 @@
+//! The middleware must wrap any service of requests with a `http::Response`,
+//! whatever its body type, without needing the service to be cloneable.
+
+use http::{Method, Request, Response};
+use hyper_method_override_middleware::MethodOverrideMiddleware;
+use std::convert::Infallible;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// A request body that is neither `http_body::Body` nor `From<Bytes>`, such as
+/// one from another version of the `http-body` crate.
+struct OpaqueBody;
+
+/// A service that can't be cloned, such as a tower `BoxService`.
+struct UniqueService;
+
+impl Service<Request<OpaqueBody>> for UniqueService {
+    type Response = Response<String>;
+    type Error = Infallible;
+    type Future = std::future::Ready<Result<Response<String>, Infallible>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<OpaqueBody>) -> Self::Future {
+        std::future::ready(Ok(Response::new(req.method().to_string())))
+    }
+}
+
+#[tokio::test]
+async fn non_clone_service_test() {
+    let mut service = MethodOverrideMiddleware::new(UniqueService);
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri("/?_method=PUT")
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body(OpaqueBody)
+        .unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.into_body(), "PUT");
+}
@@ -0,0 +1,205 @@
+//! Serves the hyper 1 services over TCP with hyper-util's server builder, as
+//! applications do, both as hyper services and as tower services adapted by
+//! `TowerToHyperService`.
+#![cfg(feature = "hyper-1")]
+
+use http_body_util::{BodyExt, Full};
+use hyper1::body::{Body, Bytes, Frame, Incoming};
+use hyper1::client::conn::http1;
+use hyper1::header::{ALLOW, CONTENT_TYPE};
+use hyper1::{Request, Response, StatusCode};
+use hyper_method_override_middleware::MethodOverrideMiddleware;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio1::net::{TcpListener, TcpStream};
+
+async fn handle(req: Request<Incoming>) -> Result<Response<String>, Infallible> {
+    Ok(Response::new(format!("{} {}", req.method(), req.uri())))
+}
+
+async fn handle_form(req: Request<FormBody>) -> Result<Response<String>, Infallible> {
+    let head = format!("{} {} ", req.method(), req.uri());
+    let body = req.into_body().collect().await.unwrap().to_bytes();
+    Ok(Response::new(head + std::str::from_utf8(&body).unwrap()))
+}
+
+/// A request body still streaming from hyper, or read and replaced by
+/// `hyper1::BodyMethodOverride`, as applications serving it without a
+/// framework's body type need.
+enum FormBody {
+    Incoming(Incoming),
+    Read(Full<Bytes>),
+}
+
+impl Body for FormBody {
+    type Data = Bytes;
+    type Error = hyper1::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, hyper1::Error>>> {
+        match self.get_mut() {
+            FormBody::Incoming(body) => Pin::new(body).poll_frame(cx),
+            FormBody::Read(body) => Pin::new(body)
+                .poll_frame(cx)
+                .map_err(|never| match never {}),
+        }
+    }
+}
+
+impl From<Bytes> for FormBody {
+    fn from(body: Bytes) -> Self {
+        FormBody::Read(Full::new(body))
+    }
+}
+
+impl Default for FormBody {
+    fn default() -> Self {
+        FormBody::Read(Full::default())
+    }
+}
+
+/// Serves connections with the service on a new port until the test ends.
+async fn serve<S>(service: S) -> SocketAddr
+where
+    S: hyper1::service::Service<Request<Incoming>, Response = Response<String>>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio1::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let service = service.clone();
+            tokio1::spawn(async move {
+                auto::Builder::new(TokioExecutor::new())
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                    .unwrap();
+            });
+        }
+    });
+    addr
+}
+
+async fn send(addr: SocketAddr, uri: &str) -> (StatusCode, Option<String>, String) {
+    send_form(addr, uri, "").await
+}
+
+async fn send_form(
+    addr: SocketAddr,
+    uri: &str,
+    body: &'static str,
+) -> (StatusCode, Option<String>, String) {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (mut sender, connection) = http1::handshake(TokioIo::new(stream)).await.unwrap();
+    tokio1::spawn(connection);
+
+    let req = Request::post(uri)
+        .header("host", addr.to_string())
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Full::new(Bytes::from(body)))
+        .unwrap();
+    let res = sender.send_request(req).await.unwrap();
+    let status = res.status();
+    let allow = res
+        .headers()
+        .get(ALLOW)
+        .map(|allow| allow.to_str().unwrap().to_string());
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    (status, allow, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio1::test(crate = "tokio1")]
+async fn serve_connection_test() {
+    let service = MethodOverrideMiddleware::builder()
+        .strict(true)
+        .build_hyper1(hyper1::service::service_fn(handle));
+    let addr = serve(service).await;
+
+    assert_eq!(
+        send(addr, "/item?a=1&_method=DELETE").await,
+        (StatusCode::OK, None, "DELETE /item?a=1".to_string())
+    );
+    assert_eq!(
+        send(addr, "/item?_method=TRACE").await,
+        (
+            StatusCode::METHOD_NOT_ALLOWED,
+            Some("POST, PUT, PATCH, DELETE".to_string()),
+            "".to_string()
+        )
+    );
+}
+
+#[cfg(feature = "tower")]
+#[tokio1::test(crate = "tokio1")]
+async fn tower_to_hyper_service_test() {
+    use hyper_method_override_middleware::hyper1::MethodOverride;
+    use hyper_util::service::TowerToHyperService;
+
+    let service = MethodOverride::new(tower::service_fn(handle));
+    let addr = serve(TowerToHyperService::new(service)).await;
+
+    assert_eq!(
+        send(addr, "/item?_method=PUT").await,
+        (StatusCode::OK, None, "PUT /item".to_string())
+    );
+    assert_eq!(
+        send(addr, "/item?_method=GET").await,
+        (StatusCode::OK, None, "POST /item?_method=GET".to_string())
+    );
+}
+
+#[tokio1::test(crate = "tokio1")]
+async fn form_body_test() {
+    use hyper1::service::Service;
+
+    let service = MethodOverrideMiddleware::builder()
+        .form_body(true)
+        .build_hyper1_with_body(hyper1::service::service_fn(handle_form));
+    let service = hyper1::service::service_fn(move |req: Request<Incoming>| {
+        service.call(req.map(FormBody::Incoming))
+    });
+    let addr = serve(service).await;
+
+    assert_eq!(
+        send_form(addr, "/item", "a=1&_method=DELETE").await,
+        (
+            StatusCode::OK,
+            None,
+            "DELETE /item a=1&_method=DELETE".to_string()
+        )
+    );
+    assert_eq!(
+        send_form(addr, "/item", "_method=GET").await,
+        (StatusCode::OK, None, "POST /item _method=GET".to_string())
+    );
+}
+
+#[cfg(feature = "tower")]
+#[tokio1::test(crate = "tokio1")]
+async fn tower_form_body_test() {
+    use hyper_util::service::TowerToHyperService;
+    use tower::ServiceExt;
+
+    let service = MethodOverrideMiddleware::builder()
+        .form_body(true)
+        .build_hyper1_with_body(tower::service_fn(handle_form))
+        .map_request(|req: Request<Incoming>| req.map(FormBody::Incoming));
+    let addr = serve(TowerToHyperService::new(service)).await;
+
+    assert_eq!(
+        send_form(addr, "/item", "_method=PUT").await,
+        (StatusCode::OK, None, "PUT /item _method=PUT".to_string())
+    );
+}
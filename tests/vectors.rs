@@ -0,0 +1,268 @@
+//! Runs the conformance vectors in `tests/vectors/` through the middleware.
+
+use hyper::service::{service_fn, Service};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use hyper_method_override_middleware::{BodyPolicy, Builder, MethodOverrideMiddleware, Precedence};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Vector {
+    description: String,
+    #[serde(default)]
+    config: VectorConfig,
+    request: VectorRequest,
+    expected: VectorExpected,
+}
+
+/// The `Builder` settings the vector is run with. Those left out keep their
+/// defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VectorConfig {
+    param_name: Option<String>,
+    allowed_methods: Option<Vec<String>>,
+    source_methods: Option<Vec<String>>,
+    header_override: Option<bool>,
+    precedence: Option<String>,
+    check_content_type: Option<bool>,
+    content_types: Option<Vec<String>>,
+    strict: Option<bool>,
+    strip_param: Option<bool>,
+    hint_supported_methods: Option<bool>,
+    require_cookie: Option<String>,
+    #[serde(default)]
+    include_prefixes: Vec<String>,
+    #[serde(default)]
+    exclude_prefixes: Vec<String>,
+    #[serde(default)]
+    body_policies: BTreeMap<String, String>,
+    form_body: Option<bool>,
+    form_body_limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VectorRequest {
+    method: String,
+    uri: String,
+    #[serde(default)]
+    headers: Vec<(String, String)>,
+    #[serde(default)]
+    body: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VectorExpected {
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    uri: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    status: Option<u16>,
+    #[serde(default)]
+    headers: Vec<(String, String)>,
+}
+
+fn load_vectors() -> Vec<(String, Vector)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors");
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("json".as_ref()))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .flat_map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let vectors: Vec<Vector> = serde_json::from_slice(&fs::read(&path).unwrap())
+                .unwrap_or_else(|error| panic!("{}: {}", name, error));
+            vectors
+                .into_iter()
+                .map(move |vector| (name.clone(), vector))
+        })
+        .collect()
+}
+
+fn build_options(config: &VectorConfig) -> Builder {
+    let mut builder = MethodOverrideMiddleware::builder();
+    if let Some(name) = &config.param_name {
+        builder = builder.param_name(name.as_str());
+    }
+    if let Some(methods) = &config.allowed_methods {
+        builder = builder.allowed_methods(methods);
+    }
+    if let Some(methods) = &config.source_methods {
+        builder = builder.source_methods(methods);
+    }
+    if let Some(enabled) = config.header_override {
+        builder = builder.header_override(enabled);
+    }
+    if let Some(precedence) = &config.precedence {
+        builder = builder.precedence(match precedence.as_str() {
+            "query" => Precedence::Query,
+            "header" => Precedence::Header,
+            other => panic!("unknown precedence {:?}", other),
+        });
+    }
+    if let Some(enabled) = config.check_content_type {
+        builder = builder.check_content_type(enabled);
+    }
+    if let Some(content_types) = &config.content_types {
+        builder = builder.content_types(content_types.iter().map(String::as_str));
+    }
+    if let Some(enabled) = config.strict {
+        builder = builder.strict(enabled);
+    }
+    if let Some(enabled) = config.strip_param {
+        builder = builder.strip_param(enabled);
+    }
+    if let Some(hint) = config.hint_supported_methods {
+        builder = builder.hint_supported_methods(hint);
+    }
+    if let Some(name) = &config.require_cookie {
+        builder = builder.require_cookie(name.as_str());
+    }
+    for prefix in &config.include_prefixes {
+        builder = builder.include_prefix(prefix.as_str());
+    }
+    for prefix in &config.exclude_prefixes {
+        builder = builder.exclude_prefix(prefix.as_str());
+    }
+    for (method, policy) in &config.body_policies {
+        let policy = match policy.as_str() {
+            "keep" => BodyPolicy::Keep,
+            "strip" => BodyPolicy::Strip,
+            "reject" => BodyPolicy::Reject,
+            other => panic!("unknown body policy {:?}", other),
+        };
+        builder = builder.body_policy(method, policy);
+    }
+    if let Some(enabled) = config.form_body {
+        builder = builder.form_body(enabled);
+    }
+    if let Some(limit) = config.form_body_limit {
+        builder = builder.form_body_limit(limit);
+    }
+    builder
+}
+
+fn build_request(vector: &VectorRequest) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(vector.method.as_str())
+        .uri(vector.uri.as_str());
+    for (name, value) in &vector.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    builder.body(vector.body.clone().into()).unwrap()
+}
+
+async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let head = format!("{}\n{}\n", req.method(), req.uri());
+    let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+    Ok(Response::new([head.as_bytes(), &body].concat().into()))
+}
+
+/// What the wrapped service saw of the request, if it was called.
+#[derive(Debug)]
+struct Seen {
+    method: String,
+    uri: String,
+    body: String,
+}
+
+async fn send<S>(mut service: S, req: Request<Body>) -> (Response<Body>, Option<Seen>)
+where
+    S: Service<Request<Body>, Response = Response<Body>, Error = Infallible>,
+{
+    std::future::poll_fn(|cx| service.poll_ready(cx))
+        .await
+        .unwrap();
+    let response = service.call(req).await.unwrap();
+    let (parts, body) = response.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+
+    // The middleware's own responses have an empty body.
+    let mut lines = body.splitn(3, '\n');
+    let seen = match (lines.next(), lines.next(), lines.next()) {
+        (Some(method), Some(uri), Some(body)) => Some(Seen {
+            method: method.to_string(),
+            uri: uri.to_string(),
+            body: body.to_string(),
+        }),
+        _ => None,
+    };
+    (Response::from_parts(parts, Body::empty()), seen)
+}
+
+async fn run(vector: &Vector) -> (Response<Body>, Option<Seen>) {
+    let builder = build_options(&vector.config);
+    let req = build_request(&vector.request);
+    let bodies = vector.config.form_body == Some(true)
+        || vector
+            .config
+            .body_policies
+            .values()
+            .any(|policy| policy == "strip");
+    if bodies {
+        send(builder.build_with_body(service_fn(handle)), req).await
+    } else {
+        send(builder.build(service_fn(handle)), req).await
+    }
+}
+
+#[tokio::test]
+async fn vectors_test() {
+    let vectors = load_vectors();
+    assert!(!vectors.is_empty());
+
+    for (file, vector) in vectors {
+        let context = format!("{}: {}", file, vector.description);
+        let (response, seen) = run(&vector).await;
+        let expected = &vector.expected;
+
+        let status = expected.status.unwrap_or(200);
+        assert_eq!(
+            response.status(),
+            StatusCode::from_u16(status).unwrap(),
+            "{}",
+            context
+        );
+        for (name, value) in &expected.headers {
+            assert_eq!(
+                response
+                    .headers()
+                    .get(name.as_str())
+                    .map(|value| value.to_str().unwrap()),
+                Some(value.as_str()),
+                "{}",
+                context
+            );
+        }
+
+        let seen = match (&expected.method, seen) {
+            (Some(_), Some(seen)) => seen,
+            (None, None) => continue,
+            (Some(_), None) => panic!("{}: the wrapped service wasn't called", context),
+            (None, Some(seen)) => panic!("{}: the wrapped service saw {:?}", context, seen),
+        };
+        let method: Method = expected.method.as_ref().unwrap().parse().unwrap();
+        assert_eq!(seen.method, method.as_str(), "{}", context);
+        if let Some(uri) = &expected.uri {
+            assert_eq!(&seen.uri, uri, "{}", context);
+        }
+        if let Some(body) = &expected.body {
+            assert_eq!(&seen.body, body, "{}", context);
+        }
+    }
+}